@@ -0,0 +1,90 @@
+//! Turns the levels in `levels/waves.txt` into `const` data, which
+//! `src/game_logic/levels.rs` includes.
+
+use std::{env, fmt::Write, fs, path::Path};
+
+#[path = "build/level_format.rs"]
+mod level_format;
+
+use level_format::{Cell, Level, Movement};
+
+const LEVELS: &str = "levels/waves.txt";
+
+fn main() {
+    generate_levels(Path::new(""));
+}
+
+/// `root` is the firmware's directory, relative to the package being built.
+/// `tools/host-tests` builds the same levels from there.
+pub fn generate_levels(root: &Path) {
+    let levels_path = root.join(LEVELS);
+    let levels_path = levels_path.display();
+    println!("cargo::rerun-if-changed={levels_path}");
+    println!(
+        "cargo::rerun-if-changed={}",
+        root.join("build/level_format.rs").display()
+    );
+
+    let text = fs::read_to_string(root.join(LEVELS))
+        .unwrap_or_else(|e| panic!("reading {levels_path}: {e}"));
+    let levels = match level_format::parse(&text) {
+        Ok(levels) => levels,
+        Err(e) => {
+            // Point at the offending line, in a form editors can jump to.
+            println!("cargo::error={levels_path}:{}: {}", e.line, e.message);
+            return;
+        }
+    };
+
+    let mut out = String::new();
+    for level in &levels {
+        write_level(&mut out, level);
+    }
+
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("levels.rs");
+    fs::write(path, out).unwrap();
+}
+
+fn write_level(out: &mut String, level: &Level) {
+    let movement = match level.movement {
+        Movement::Shuffle => "Shuffle",
+        Movement::March => "March",
+        Movement::Zigzag => "Zigzag",
+    };
+
+    writeln!(
+        out,
+        "/// `[wave {}]`, from line {}.",
+        level.name, level.line
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "pub const {}: Wave = Wave {{",
+        level.name.to_uppercase()
+    )
+    .unwrap();
+    writeln!(out, "    formations: &[").unwrap();
+    for formation in &level.formations {
+        writeln!(out, "        [").unwrap();
+        for row in formation {
+            let cells: Vec<&str> = row
+                .iter()
+                .map(|cell| match cell {
+                    Cell::Empty => "None",
+                    Cell::Grunt => "Some(EnemyKind::Grunt)",
+                    Cell::Armoured => "Some(EnemyKind::Armoured)",
+                    Cell::Splitter => "Some(EnemyKind::Splitter)",
+                })
+                .collect();
+            writeln!(out, "            [{}],", cells.join(", ")).unwrap();
+        }
+        writeln!(out, "        ],").unwrap();
+    }
+    writeln!(out, "    ],").unwrap();
+    writeln!(out, "    shields: {:?},", level.shields).unwrap();
+    writeln!(out, "    movement: Movement::{movement},").unwrap();
+    writeln!(out, "    march_speed_percent: {},", level.speed).unwrap();
+    writeln!(out, "    divers: {},", level.divers).unwrap();
+    writeln!(out, "}};").unwrap();
+}
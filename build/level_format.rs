@@ -0,0 +1,303 @@
+//! Parses the level files in `levels/`.
+//!
+//! This runs on the host, not the micro:bit: it's shared by `build.rs`, which
+//! turns the levels into `const` data, and by `tools/level-preview`. See
+//! `levels/waves.txt` for a description of the format.
+
+use std::fmt;
+
+/// The display is a square grid with this many rows and columns.
+pub const SIZE: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Empty,
+    Grunt,
+    Armoured,
+    Splitter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Movement {
+    Shuffle,
+    March,
+    Zigzag,
+}
+
+pub type Formation = [[Cell; SIZE]; SIZE];
+
+#[derive(Debug, Clone)]
+pub struct Level {
+    pub name: String,
+    /// Where the level starts in the file, counting from 1.
+    pub line: usize,
+    pub movement: Movement,
+    /// How fast the enemies march, as a percentage of the usual speed.
+    pub speed: u32,
+    pub divers: bool,
+    pub shields: [bool; SIZE],
+    pub formations: Vec<Formation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// Counting from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn error<T>(line: usize, message: impl Into<String>) -> Result<T, Error> {
+    Err(Error {
+        line,
+        message: message.into(),
+    })
+}
+
+/// A level that's still being parsed. Keys are optional until the level ends,
+/// so we can tell whether they've been given yet.
+struct Partial {
+    name: String,
+    line: usize,
+    movement: Option<Movement>,
+    speed: Option<u32>,
+    divers: Option<bool>,
+    shields: Option<[bool; SIZE]>,
+    formations: Vec<Formation>,
+    /// The formation currently being read, and the line it started on.
+    formation: Option<(Vec<[Cell; SIZE]>, usize)>,
+}
+
+impl Partial {
+    fn finish_formation(&mut self) -> Result<(), Error> {
+        let Some((rows, line)) = self.formation.take() else {
+            return Ok(());
+        };
+        if rows.is_empty() {
+            return error(line, "formation has no rows");
+        }
+        if rows.iter().flatten().all(|&cell| cell == Cell::Empty) {
+            return error(line, "formation has no enemies");
+        }
+
+        let mut formation = [[Cell::Empty; SIZE]; SIZE];
+        formation[..rows.len()].copy_from_slice(&rows);
+        self.formations.push(formation);
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Level, Error> {
+        self.finish_formation()?;
+        let Some(movement) = self.movement else {
+            return error(self.line, format!("wave `{}` has no `movement`", self.name));
+        };
+        if self.formations.is_empty() {
+            return error(self.line, format!("wave `{}` has no formations", self.name));
+        }
+        Ok(Level {
+            name: self.name,
+            line: self.line,
+            movement,
+            speed: self.speed.unwrap_or(100),
+            divers: self.divers.unwrap_or(false),
+            shields: self.shields.unwrap_or([false; SIZE]),
+            formations: self.formations,
+        })
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<Level>, Error> {
+    let mut levels: Vec<Level> = Vec::new();
+    let mut current: Option<Partial> = None;
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let text = raw.split('#').next().unwrap_or("").trim();
+
+        // A blank line ends a formation.
+        if text.is_empty() {
+            if let Some(level) = &mut current {
+                level.finish_formation()?;
+            }
+            continue;
+        }
+
+        if let Some(header) = text.strip_prefix('[') {
+            if let Some(level) = current.take() {
+                levels.push(level.finish()?);
+            }
+            let Some(name) = header
+                .strip_suffix(']')
+                .and_then(|h| h.strip_prefix("wave "))
+            else {
+                return error(line, "expected a header like `[wave name]`");
+            };
+            let name = name.trim();
+            let valid_name = name.starts_with(|c: char| c.is_ascii_lowercase())
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid_name {
+                return error(
+                    line,
+                    format!("wave names must be lowercase letters, digits and `_`, not `{name}`"),
+                );
+            }
+            if let Some(other) = levels.iter().find(|l| l.name == name) {
+                return error(
+                    line,
+                    format!("wave `{name}` is already defined on line {}", other.line),
+                );
+            }
+            current = Some(Partial {
+                name: name.to_string(),
+                line,
+                movement: None,
+                speed: None,
+                divers: None,
+                shields: None,
+                formations: Vec::new(),
+                formation: None,
+            });
+            continue;
+        }
+
+        let Some(level) = &mut current else {
+            return error(line, "expected a header like `[wave name]` first");
+        };
+
+        if text == "formation:" {
+            level.finish_formation()?;
+            level.formation = Some((Vec::new(), line));
+            continue;
+        }
+
+        if let Some((rows, _)) = &mut level.formation {
+            if rows.len() == SIZE - 1 {
+                return error(
+                    line,
+                    format!(
+                        "formations can be at most {} rows tall, since the player is in the bottom row",
+                        SIZE - 1
+                    ),
+                );
+            }
+            rows.push(parse_row(line, text)?);
+            continue;
+        }
+
+        let Some((key, value)) = text.split_once('=') else {
+            return error(line, "expected `key = value`, or `formation:`");
+        };
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            "movement" => {
+                let movement = match value {
+                    "shuffle" => Movement::Shuffle,
+                    "march" => Movement::March,
+                    "zigzag" => Movement::Zigzag,
+                    _ => {
+                        return error(
+                            line,
+                            format!(
+                                "unknown movement `{value}`; expected shuffle, march or zigzag"
+                            ),
+                        )
+                    }
+                };
+                set(&mut level.movement, movement, line, key)?;
+            }
+            "speed" => {
+                let speed = match value.strip_suffix('%').unwrap_or(value).parse() {
+                    Ok(speed) if speed > 0 => speed,
+                    _ => {
+                        return error(
+                            line,
+                            format!("expected a positive percentage, not `{value}`"),
+                        )
+                    }
+                };
+                set(&mut level.speed, speed, line, key)?;
+            }
+            "divers" => {
+                let divers = match value {
+                    "yes" => true,
+                    "no" => false,
+                    _ => return error(line, format!("expected yes or no, not `{value}`")),
+                };
+                set(&mut level.divers, divers, line, key)?;
+            }
+            "shields" => {
+                let shields = parse_shields(line, value)?;
+                set(&mut level.shields, shields, line, key)?;
+            }
+            _ => return error(line, format!("unknown key `{key}`")),
+        }
+    }
+
+    match current {
+        Some(level) => levels.push(level.finish()?),
+        None => return error(1, "no waves defined"),
+    }
+    Ok(levels)
+}
+
+fn set<T>(slot: &mut Option<T>, value: T, line: usize, key: &str) -> Result<(), Error> {
+    if slot.is_some() {
+        return error(line, format!("`{key}` is given twice"));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+fn parse_row(line: usize, text: &str) -> Result<[Cell; SIZE], Error> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() != SIZE {
+        return error(
+            line,
+            format!("expected {SIZE} columns, found {}", chars.len()),
+        );
+    }
+
+    let mut row = [Cell::Empty; SIZE];
+    for (cell, c) in row.iter_mut().zip(chars) {
+        *cell = match c {
+            '.' => Cell::Empty,
+            'G' => Cell::Grunt,
+            'A' => Cell::Armoured,
+            'S' => Cell::Splitter,
+            _ => {
+                return error(
+                    line,
+                    format!("unknown enemy `{c}`; expected G, A, S, or `.` for none"),
+                )
+            }
+        };
+    }
+    Ok(row)
+}
+
+fn parse_shields(line: usize, value: &str) -> Result<[bool; SIZE], Error> {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() != SIZE {
+        return error(
+            line,
+            format!("expected {SIZE} columns of shields, found {}", chars.len()),
+        );
+    }
+
+    let mut shields = [false; SIZE];
+    for (shield, c) in shields.iter_mut().zip(chars) {
+        *shield = match c {
+            'X' => true,
+            '.' => false,
+            _ => return error(line, format!("expected X or `.` for shields, not `{c}`")),
+        };
+    }
+    Ok(shields)
+}
@@ -1,139 +1,210 @@
-use core::ops::Deref;
+use core::{
+    arch::asm,
+    sync::atomic::{compiler_fence, Ordering::SeqCst},
+};
 
 use nrf52833_hal::{
     gpio::{
         p0::{P0_14, P0_23},
-        Disconnected,
+        Disconnected, Floating, Input, Pin,
     },
-    pac::timer0,
+    gpiote::Gpiote,
+    prelude::InputPin,
 };
+use void::ResultVoidExt;
 
-use self::button::{Button, ButtonState};
-use crate::wrapping_timer::WrappingTimer;
+use self::{
+    button::{ButtonState, DebounceAlgorithm},
+    chords::Chords,
+    gestures::Gestures,
+};
+use crate::log::log;
 
 pub mod button;
+mod chords;
+mod events;
+mod gestures;
+
+pub use self::{
+    chords::AutoRepeat,
+    events::{ButtonAction, ButtonEvent, ButtonId},
+    gestures::GestureTiming,
+};
 
-/// When the user presses a button, how long do we wait to see if they press the
-/// other button as well? In microseconds.
-const PRESS_AND_HOLD_TIMEOUT_US: u32 = 100_000; // 100,000 us = 100 ms
-
-/// Input handling for the Space Invaders game.
+/// Input handling for the Space Invaders game: reads the A and B buttons, and
+/// leaves making sense of them to `Chords` and `Gestures`.
 ///
-/// Press A or B to move left or right. Press both at once to fire.
-pub struct Buttons<T: Deref<Target = timer0::RegisterBlock>> {
-    buttons: [Button; 2],
-    time_source: WrappingTimer<T>,
-    /// If either button is held, this is the timestamp of when it was
-    /// initially pressed. At most one button is held at a time.
-    held_since: Option<u32>,
+/// The pins can either be polled all the time, or only once GPIOTE reports that
+/// one of them has changed. See `arm_for_change`.
+pub struct Buttons {
+    pins: [Pin<Input<Floating>>; 2],
+    /// What the pins read last time, to log changes. See `tools/debounce-bench`.
+    last_states: [ButtonState; 2],
+    chords: Chords,
+    gestures: [Gestures; 2],
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ButtonAction {
-    Left,
-    Right,
-    Fire,
-}
+impl Buttons {
+    /// Each button can be debounced differently. Without `auto_repeat`,
+    /// holding the buttons only does anything once.
+    pub fn new(
+        a: P0_14<Disconnected>,
+        b: P0_23<Disconnected>,
+        debounce: [DebounceAlgorithm; 2],
+        auto_repeat: Option<AutoRepeat>,
+        gesture_timing: GestureTiming,
+    ) -> Self {
+        let pins = [
+            a.degrade().into_floating_input(),
+            b.degrade().into_floating_input(),
+        ];
 
-impl<T: Deref<Target = timer0::RegisterBlock>> Buttons<T> {
-    pub fn new(a: P0_14<Disconnected>, b: P0_23<Disconnected>, time_source: T) -> Self {
-        let mut this = Self {
-            buttons: [Button::new(a.degrade()), Button::new(b.degrade())],
-            time_source: WrappingTimer::new(time_source),
-            held_since: None,
-        };
-
-        // The `update` logic is simpler if we can assume both buttons start
-        // "not-pressed".
-        for b in &mut this.buttons {
-            if b.state().is_pressed() {
-                b.force_state_change(ButtonState::NotPressed);
-            }
+        // Configuring the pin and then immediately reading from it sometimes
+        // produces the wrong value. This leads us to believe the button is
+        // pressed, even when it's not.
+        //
+        // Pausing for one CPU cycle seems to fix the issue.
+        //
+        // Possible explanation here:
+        // https://devzone.nordicsemi.com/f/nordic-q-a/36881/best-practice---delay-between-setting-pin-as-input-with-pull-up-pull-down-and-reading-the-pin/141580
+        // additional context here: (page 16)
+        // https://infocenter.nordicsemi.com/pdf/nRF52_Series_Migration.pdf
+        // and here: (at the bottom of page 100)
+        // https://infocenter.nordicsemi.com/pdf/nRF52833_PS_v1.6.pdf
+        //
+        // One issue with this explanation is that the GPIO peripheral is
+        // connected to the *AHB*, not the APB. As far as I can tell, the AHB
+        // doesn't buffer writes.
+
+        // Insert a "nop" between configuring the pin and reading it.
+        compiler_fence(SeqCst);
+        // SAFETY: I'll be honest, I haven't read the
+        // [rules](https://doc.rust-lang.org/reference/inline-assembly.html#rules-for-inline-assembly),
+        // but inserting a single "no-operation" instruction seems like a pretty
+        // harmless use of inline assembly, so I'll assume there's no undefined
+        // behaviour here.
+        unsafe { asm!("nop") };
+        compiler_fence(SeqCst);
+
+        let last_states = pins.each_ref().map(read_state);
+        let chords = Chords::new(last_states, debounce, auto_repeat);
+        Self {
+            pins,
+            last_states,
+            chords,
+            gestures: [
+                Gestures::new(ButtonId::A, gesture_timing),
+                Gestures::new(ButtonId::B, gesture_timing),
+            ],
         }
-
-        this
     }
 
     /// You must call this every `button::BUTTON_TIMER_US` microseconds for it
-    /// to work correctly.
-    pub fn update(&mut self) -> Option<ButtonAction> {
-        debug_assert!({
-            let both_pressed = self.buttons.iter().all(|b| b.state().is_pressed());
-            !both_pressed
+    /// to work correctly, passing the current time. The exception is when
+    /// `is_settled`: then it's fine to wait until a pin changes.
+    pub fn update(&mut self, now_us: u32) -> impl Iterator<Item = ButtonEvent> {
+        let states = self.pins.each_ref().map(read_state);
+        // `i` is only used for logging.
+        #[cfg_attr(not(feature = "log-buttons"), allow(unused_variables))]
+        for (i, (&state, last)) in states.iter().zip(&mut self.last_states).enumerate() {
+            if state != *last {
+                // In the same `<time> <level>` format as the bench's traces.
+                log!(
+                    buttons,
+                    trace,
+                    "pin {} {} {}",
+                    i,
+                    now_us,
+                    state.is_pressed() as u8
+                );
+                *last = state;
+            }
+        }
+        let action = self.chords.update(now_us, states);
+
+        let [a, b] = [0, 1].map(|i| {
+            let held = self.chords.buttons()[i].is_held();
+            self.gestures[i].update(now_us, held)
         });
+        a.into_iter()
+            .chain(b)
+            .chain([action.map(ButtonEvent::Action)])
+            .flatten()
+    }
 
-        let actions = [
-            self.check_timeout(),
-            self.update_button(0),
-            self.update_button(1),
-        ];
+    pub fn is_settled(&self) -> bool {
+        self.chords.is_settled() && self.gestures.iter().all(Gestures::is_settled)
+    }
+
+    /// Send the input statistics over the debug channel.
+    #[cfg_attr(not(feature = "log-buttons"), allow(unused_variables))]
+    pub fn dump_stats(&self) {
+        for (i, button) in self.chords.buttons().iter().enumerate() {
+            let stats = button.stats();
+            log!(
+                buttons,
+                info,
+                "button {}: {} presses, {} ms on average, {} bounces, longest {} us",
+                i,
+                stats.presses,
+                stats.total_press_ms.checked_div(stats.presses).unwrap_or(0),
+                stats.bounces,
+                stats.longest_bounce_us,
+            );
+        }
+        let stats = self.chords.stats();
+        log!(
+            buttons,
+            info,
+            "chords: {}, missed {}",
+            stats.chords,
+            stats.misses
+        );
+    }
 
-        debug_assert!({
-            let num_actions = actions.iter().flatten().count();
-            num_actions <= 1
+    /// Set up a GPIOTE port event for when either pin changes from its settled
+    /// state. Only call this when `is_settled`.
+    pub fn arm_for_change(&self, gpiote: &Gpiote) {
+        self.arm(gpiote, |i| {
+            self.chords.buttons()[i]
+                .settled_state()
+                .expect("buttons are settled")
         });
+    }
 
-        actions.into_iter().flatten().next()
+    /// Set up a GPIOTE port event for when either button is pressed.
+    pub fn arm_for_press(&self, gpiote: &Gpiote) {
+        self.arm(gpiote, |_| ButtonState::NotPressed);
     }
 
-    /// Check if a button has been held down for more than a short moment. If
-    /// so, release the button.
-    ///
-    /// This makes movement feel more responsive. The player probably expects
-    /// actions to happen when they *press* buttons, not when they release them.
-    ///
-    /// Instead of reacting *immediately* on-press, we wait briefly to see if
-    /// the user presses the other button as well. That way we can still detect
-    /// the user pressing both buttons "at the same time".
-    fn check_timeout(&mut self) -> Option<ButtonAction> {
-        if let Some(t) = self.held_since {
-            if self.time_source.elapsed_us(t) >= PRESS_AND_HOLD_TIMEOUT_US {
-                // Release whichever button was held.
-                for i in 0..2 {
-                    if self.buttons[i].state().is_pressed() {
-                        self.buttons[i].force_state_change(ButtonState::NotPressed);
-                        self.held_since = None;
-                        return Some(ButtonAction::left_right(i));
-                    }
-                }
-                debug_assert!(false);
-            }
+    /// Stop the port event set up by `arm_for_change` or `arm_for_press`.
+    pub fn disarm(&self, gpiote: &Gpiote) {
+        for pin in &self.pins {
+            gpiote.port().input_pin(pin).disabled();
         }
-        None
     }
 
-    /// Check a button for state changes.
-    fn update_button(&mut self, i: usize) -> Option<ButtonAction> {
-        match self.buttons[i].update() {
-            None => None,
-            Some(ButtonState::Pressed) => {
-                // Is the other button also pressed?
-                if self.buttons[1 - i].state().is_pressed() {
-                    // Treat both buttons as released, so the player doesn't
-                    // move they physically release the buttons.
-                    self.buttons[i].force_state_change(ButtonState::NotPressed);
-                    self.buttons[1 - i].force_state_change(ButtonState::NotPressed);
-                    self.held_since = None;
-                    Some(ButtonAction::Fire)
-                } else {
-                    self.held_since = Some(self.time_source.curr_time_us());
-                    None
-                }
-            }
-            Some(ButtonState::NotPressed) => {
-                self.held_since = None;
-                Some(ButtonAction::left_right(i))
+    /// Fire a port event as soon as any pin is no longer in `state(i)`.
+    fn arm(&self, gpiote: &Gpiote, state: impl Fn(usize) -> ButtonState) {
+        // Clear the event first: if a pin has already changed, setting its
+        // sense level raises a fresh one.
+        gpiote.port().reset_events();
+        for (i, pin) in self.pins.iter().enumerate() {
+            // Note: low = pressed.
+            match state(i) {
+                ButtonState::NotPressed => gpiote.port().input_pin(pin).low(),
+                ButtonState::Pressed => gpiote.port().input_pin(pin).high(),
             }
         }
+        gpiote.port().enable_interrupt();
     }
 }
 
-impl ButtonAction {
-    fn left_right(i: usize) -> Self {
-        match i {
-            0 => Self::Left,
-            1 => Self::Right,
-            _ => panic!("buttons are indexed with 0 or 1, got: {i}"),
-        }
+fn read_state(pin: &Pin<Input<Floating>>) -> ButtonState {
+    // Note: low = pressed.
+    if pin.is_low().void_unwrap() {
+        ButtonState::Pressed
+    } else {
+        ButtonState::NotPressed
     }
 }
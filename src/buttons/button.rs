@@ -1,22 +1,42 @@
-use core::{
-    arch::asm,
-    sync::atomic::{compiler_fence, Ordering::SeqCst},
-};
+use self::debouncer::{Debounce, Debouncer};
 
-use nrf52833_hal::{
-    gpio::{Disconnected, Floating, Input, Pin},
-    prelude::InputPin,
-};
-use void::ResultVoidExt;
+mod debouncer;
 
-use self::debouncer::Debouncer;
+#[cfg(test)]
+mod tests;
 
-mod debouncer;
+/// If the pin changes again this soon after changing, it's bouncing rather
+/// than being pressed or released. In microseconds.
+const BOUNCE_WINDOW_US: u32 = 20_000; // 20,000 us = 20 ms
 
+/// One button, after debouncing. Feed it what the pin reads with `update`.
 pub struct Button {
-    pin: Pin<Input<Floating>>,
     state: ButtonState,
     debouncer: Debouncer,
+    /// What the pin read last time.
+    last_sample: ButtonState,
+    /// When the pin last changed.
+    last_change_us: Option<u32>,
+    /// When the pin started bouncing, if it's been bouncing since.
+    bounce_start_us: u32,
+    /// When the button was pressed, if it's physically held down.
+    pressed_since: Option<u32>,
+    stats: ButtonStats,
+}
+
+/// Counters for telling whether a switch is wearing out: worn switches bounce
+/// more, and for longer.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ButtonStats {
+    /// How many times the pin changed again straight after changing.
+    pub bounces: u32,
+    /// The longest the pin has kept bouncing for, in microseconds.
+    pub longest_bounce_us: u32,
+    /// Physical presses, whether or not `Button::state` saw them.
+    pub presses: u32,
+    /// How long the button has been held down for, over all the `presses`, in
+    /// milliseconds.
+    pub total_press_ms: u32,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -26,42 +46,16 @@ pub enum ButtonState {
 }
 
 impl Button {
-    pub fn new(pin: Pin<Disconnected>) -> Self {
-        let pin = pin.into_floating_input();
-
-        // Configuring the pin and then immediately reading from it sometimes
-        // produces the wrong value. This leads us to believe the button is
-        // pressed, even when it's not.
-        //
-        // Pausing for one CPU cycle seems to fix the issue.
-        //
-        // Possible explanation here:
-        // https://devzone.nordicsemi.com/f/nordic-q-a/36881/best-practice---delay-between-setting-pin-as-input-with-pull-up-pull-down-and-reading-the-pin/141580
-        // additional context here: (page 16)
-        // https://infocenter.nordicsemi.com/pdf/nRF52_Series_Migration.pdf
-        // and here: (at the bottom of page 100)
-        // https://infocenter.nordicsemi.com/pdf/nRF52833_PS_v1.6.pdf
-        //
-        // One issue with this explanation is that the GPIO peripheral is
-        // connected to the *AHB*, not the APB. As far as I can tell, the AHB
-        // doesn't buffer writes.
-
-        // Insert a "nop" between configuring the pin and reading it.
-        compiler_fence(SeqCst);
-        // SAFETY: I'll be honest, I haven't read the
-        // [rules](https://doc.rust-lang.org/reference/inline-assembly.html#rules-for-inline-assembly),
-        // but inserting a single "no-operation" instruction seems like a pretty
-        // harmless use of inline assembly, so I'll assume there's no undefined
-        // behaviour here.
-        unsafe { asm!("nop") };
-        compiler_fence(SeqCst);
-
-        let initial_state = read_state(&pin);
-
+    /// `initial_state` is what the pin reads right now.
+    pub fn new(initial_state: ButtonState, debounce: DebounceAlgorithm) -> Self {
         Self {
-            pin,
             state: initial_state,
-            debouncer: Debouncer::new(initial_state),
+            debouncer: Debouncer::new(debounce, initial_state),
+            last_sample: initial_state,
+            last_change_us: None,
+            bounce_start_us: 0,
+            pressed_since: None,
+            stats: ButtonStats::default(),
         }
     }
 
@@ -69,14 +63,37 @@ impl Button {
         self.state
     }
 
+    /// Is the button physically held down? Unlike `state`, this ignores
+    /// `force_state_change`.
+    pub fn is_held(&self) -> bool {
+        self.pressed_since.is_some()
+    }
+
+    pub fn stats(&self) -> &ButtonStats {
+        &self.stats
+    }
+
+    /// What the pin has settled on, or `None` if it's in the middle of
+    /// changing. This is the physical state, even if `force_state_change`
+    /// says otherwise.
+    pub fn settled_state(&self) -> Option<ButtonState> {
+        self.debouncer.settled_state()
+    }
+
     /// Returns `Some` whenever `state` changes.
     ///
     /// You must call this every `BUTTON_TIMER_US` microseconds for it to work
-    /// correctly.
-    pub fn update(&mut self) -> Option<ButtonState> {
-        match self.debouncer.update(read_state(&self.pin)) {
+    /// correctly, passing the current time. Timestamps may wrap around.
+    pub fn update(&mut self, now_us: u32, new_state: ButtonState) -> Option<ButtonState> {
+        if new_state != self.last_sample {
+            self.last_sample = new_state;
+            self.count_bounce(now_us);
+        }
+
+        match self.debouncer.update(new_state) {
             None => None,
             Some(new_state) => {
+                self.count_press(now_us, new_state);
                 if new_state == self.state {
                     None
                 } else {
@@ -95,20 +112,42 @@ impl Button {
     pub fn force_state_change(&mut self, new_state: ButtonState) {
         self.state = new_state;
     }
+
+    /// The pin just changed. Was that a bounce?
+    fn count_bounce(&mut self, now_us: u32) {
+        match self.last_change_us {
+            Some(t) if now_us.wrapping_sub(t) < BOUNCE_WINDOW_US => {
+                self.stats.bounces += 1;
+                let bounce_us = now_us.wrapping_sub(self.bounce_start_us);
+                self.stats.longest_bounce_us = self.stats.longest_bounce_us.max(bounce_us);
+            }
+            _ => self.bounce_start_us = now_us,
+        }
+        self.last_change_us = Some(now_us);
+    }
+
+    /// The debouncer just decided the button was pressed or released.
+    fn count_press(&mut self, now_us: u32, new_state: ButtonState) {
+        match new_state {
+            ButtonState::Pressed => {
+                self.stats.presses += 1;
+                self.pressed_since = Some(now_us);
+            }
+            ButtonState::NotPressed => {
+                // A button that was held down from the start doesn't count.
+                if let Some(t) = self.pressed_since.take() {
+                    self.stats.total_press_ms += now_us.wrapping_sub(t) / 1_000;
+                }
+            }
+        }
+    }
 }
 
 /// You must call the Button's `update` method this often, so that the
 /// debouncing algorithm works correctly.
 pub use self::debouncer::DEBOUNCER_TIMER_US as BUTTON_TIMER_US;
 
-fn read_state(pin: &Pin<Input<Floating>>) -> ButtonState {
-    // Note: low = pressed.
-    if pin.is_low().void_unwrap() {
-        ButtonState::Pressed
-    } else {
-        ButtonState::NotPressed
-    }
-}
+pub use self::debouncer::Algorithm as DebounceAlgorithm;
 
 impl ButtonState {
     pub fn is_pressed(self) -> bool {
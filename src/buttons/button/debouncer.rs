@@ -1,58 +1,249 @@
-use rtt_target::rprintln;
+//! Hardware buttons are apparently notorious for "bouncing": for a few
+//! milliseconds after being pressed or released, the pin flickers between the
+//! two states.
+//!
+//! For more details, see https://www.ganssle.com/debouncing.htm
+//!
+//! There are a few ways to filter that out, trading off how quickly a press is
+//! noticed against how likely a bounce is to get through. Each button picks
+//! one with an `Algorithm`. To compare them, see `tools/debounce-bench`.
 
 use super::ButtonState;
 
 /// How often you should call the Debouncer's update method, in microseconds.
 pub const DEBOUNCER_TIMER_US: u32 = 1_000;
 
-/// After this many consecutive `update` calls with the same value, we commit to
-/// the new value.
-const NUM_CONSECUTIVE_SAMPLES: u8 = 5;
+/// A way of debouncing a button, along with its settings. Counts are in
+/// samples, i.e. calls to `update`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Change state once this many samples in a row disagree with it.
+    ///
+    /// This is the approach described in
+    /// https://www.ganssle.com/debouncing-pt2.htm
+    /// (section: "A Counting Algorithm").
+    Counting { samples: u8 },
+    /// Like `Counting`, but a press and a release can take a different number
+    /// of samples. Buttons tend to bounce more on release, so this can notice
+    /// presses sooner without letting release bounces through.
+    Asymmetric {
+        press_samples: u8,
+        release_samples: u8,
+    },
+    /// Keep a running total, which goes up for each pressed sample and down
+    /// for each released one, between 0 and `max`. The button is pressed once
+    /// the total reaches `max`, and released once it's back to 0.
+    ///
+    /// Unlike `Counting`, a stray sample doesn't start the count over; it just
+    /// sets it back by one.
+    Integrator { max: u8 },
+    /// Change state as soon as a sample disagrees, then ignore the pin for this
+    /// many samples, while it bounces.
+    ///
+    /// This notices presses the soonest, but a glitch on an idle pin gets
+    /// through as a whole press.
+    Lockout { samples: u8 },
+}
+
+impl Algorithm {
+    pub const DEFAULT: Self = Self::Counting { samples: 5 };
+}
+
+/// Common functionality of the debouncing algorithms.
+pub trait Debounce {
+    /// Filter out transient state changes. Call this every
+    /// `DEBOUNCER_TIMER_US`, with what the pin reads.
+    ///
+    /// Returns `Some` whenever the state "actually" changes.
+    fn update(&mut self, new_state: ButtonState) -> Option<ButtonState>;
+
+    /// The current state, unless it looks like it's about to change.
+    fn settled_state(&self) -> Option<ButtonState>;
+}
+
+/// Whichever debouncer an `Algorithm` picked.
+pub enum Debouncer {
+    Counting(Counting),
+    Integrator(Integrator),
+    Lockout(Lockout),
+}
+
+impl Debouncer {
+    /// None of the algorithm's counts can be zero: most of the algorithms would
+    /// stop debouncing, and `Integrator` would never release.
+    pub const fn new(algorithm: Algorithm, initial_state: ButtonState) -> Self {
+        let non_zero = match algorithm {
+            Algorithm::Counting { samples } | Algorithm::Lockout { samples } => samples > 0,
+            Algorithm::Asymmetric {
+                press_samples,
+                release_samples,
+            } => press_samples > 0 && release_samples > 0,
+            Algorithm::Integrator { max } => max > 0,
+        };
+        assert!(non_zero, "debounce sample counts must be non-zero");
+
+        match algorithm {
+            Algorithm::Counting { samples } => {
+                Self::Counting(Counting::new(initial_state, samples, samples))
+            }
+            Algorithm::Asymmetric {
+                press_samples,
+                release_samples,
+            } => Self::Counting(Counting::new(initial_state, press_samples, release_samples)),
+            Algorithm::Integrator { max } => Self::Integrator(Integrator::new(initial_state, max)),
+            Algorithm::Lockout { samples } => Self::Lockout(Lockout::new(initial_state, samples)),
+        }
+    }
+}
+
+impl Debounce for Debouncer {
+    fn update(&mut self, new_state: ButtonState) -> Option<ButtonState> {
+        match self {
+            Self::Counting(c) => c.update(new_state),
+            Self::Integrator(i) => i.update(new_state),
+            Self::Lockout(l) => l.update(new_state),
+        }
+    }
 
-/// Hardware buttons are apparently notorious for "bouncing".
-///
-/// For more details, see https://www.ganssle.com/debouncing.htm
-pub struct Debouncer {
+    fn settled_state(&self) -> Option<ButtonState> {
+        match self {
+            Self::Counting(c) => c.settled_state(),
+            Self::Integrator(i) => i.settled_state(),
+            Self::Lockout(l) => l.settled_state(),
+        }
+    }
+}
+
+/// See `Algorithm::Counting` and `Algorithm::Asymmetric`.
+pub struct Counting {
     state: ButtonState,
     count: u8,
+    press_samples: u8,
+    release_samples: u8,
 }
 
-impl Debouncer {
-    pub const fn new(initial_state: ButtonState) -> Self {
+impl Counting {
+    pub const fn new(initial_state: ButtonState, press_samples: u8, release_samples: u8) -> Self {
         Self {
             state: initial_state,
             count: 0,
+            press_samples,
+            release_samples,
         }
     }
+}
 
-    /// Filter out transient state changes.
-    ///
-    /// Returns `Some` whenever the state "actually" changes.
-    ///
-    /// This uses the debouncing approach described in
-    /// https://www.ganssle.com/debouncing-pt2.htm
-    /// (section: "A Counting Algorithm").
-    pub fn update(&mut self, new_state: ButtonState) -> Option<ButtonState> {
-        let mut changed = false;
-
-        if new_state != self.state {
-            self.count += 1;
-            if self.count >= NUM_CONSECUTIVE_SAMPLES {
-                self.state = new_state;
-                self.count = 0;
-                changed = true;
-            }
-        } else {
-            if self.count > 0 {
-                rprintln!("debounce {}", self.count);
-            }
+impl Debounce for Counting {
+    fn update(&mut self, new_state: ButtonState) -> Option<ButtonState> {
+        if new_state == self.state {
             self.count = 0;
+            return None;
         }
 
-        if changed {
-            Some(self.state)
+        let samples = match new_state {
+            ButtonState::Pressed => self.press_samples,
+            ButtonState::NotPressed => self.release_samples,
+        };
+        self.count += 1;
+        if self.count >= samples {
+            self.state = new_state;
+            self.count = 0;
+            Some(new_state)
         } else {
             None
         }
     }
+
+    fn settled_state(&self) -> Option<ButtonState> {
+        (self.count == 0).then_some(self.state)
+    }
+}
+
+/// See `Algorithm::Integrator`.
+pub struct Integrator {
+    state: ButtonState,
+    total: u8,
+    max: u8,
+}
+
+impl Integrator {
+    pub const fn new(initial_state: ButtonState, max: u8) -> Self {
+        let total = match initial_state {
+            ButtonState::NotPressed => 0,
+            ButtonState::Pressed => max,
+        };
+        Self {
+            state: initial_state,
+            total,
+            max,
+        }
+    }
+}
+
+impl Debounce for Integrator {
+    fn update(&mut self, new_state: ButtonState) -> Option<ButtonState> {
+        self.total = match new_state {
+            ButtonState::Pressed => (self.total + 1).min(self.max),
+            ButtonState::NotPressed => self.total.saturating_sub(1),
+        };
+
+        let settled_on = if self.total == self.max {
+            ButtonState::Pressed
+        } else if self.total == 0 {
+            ButtonState::NotPressed
+        } else {
+            return None;
+        };
+        if settled_on == self.state {
+            None
+        } else {
+            self.state = settled_on;
+            Some(settled_on)
+        }
+    }
+
+    fn settled_state(&self) -> Option<ButtonState> {
+        let settled = match self.state {
+            ButtonState::NotPressed => self.total == 0,
+            ButtonState::Pressed => self.total == self.max,
+        };
+        settled.then_some(self.state)
+    }
+}
+
+/// See `Algorithm::Lockout`.
+pub struct Lockout {
+    state: ButtonState,
+    /// How many more samples to ignore.
+    remaining: u8,
+    samples: u8,
+}
+
+impl Lockout {
+    pub const fn new(initial_state: ButtonState, samples: u8) -> Self {
+        Self {
+            state: initial_state,
+            remaining: 0,
+            samples,
+        }
+    }
+}
+
+impl Debounce for Lockout {
+    fn update(&mut self, new_state: ButtonState) -> Option<ButtonState> {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            None
+        } else if new_state != self.state {
+            self.state = new_state;
+            self.remaining = self.samples;
+            Some(new_state)
+        } else {
+            None
+        }
+    }
+
+    fn settled_state(&self) -> Option<ButtonState> {
+        (self.remaining == 0).then_some(self.state)
+    }
 }
@@ -0,0 +1,68 @@
+use super::{Button, ButtonState, DebounceAlgorithm};
+
+fn button(debounce: DebounceAlgorithm) -> Button {
+    Button::new(ButtonState::NotPressed, debounce)
+}
+
+#[test]
+#[should_panic(expected = "non-zero")]
+fn counting_rejects_zero_samples() {
+    button(DebounceAlgorithm::Counting { samples: 0 });
+}
+
+#[test]
+#[should_panic(expected = "non-zero")]
+fn asymmetric_rejects_zero_press_samples() {
+    button(DebounceAlgorithm::Asymmetric {
+        press_samples: 0,
+        release_samples: 8,
+    });
+}
+
+#[test]
+#[should_panic(expected = "non-zero")]
+fn asymmetric_rejects_zero_release_samples() {
+    button(DebounceAlgorithm::Asymmetric {
+        press_samples: 2,
+        release_samples: 0,
+    });
+}
+
+#[test]
+#[should_panic(expected = "non-zero")]
+fn integrator_rejects_a_zero_max() {
+    button(DebounceAlgorithm::Integrator { max: 0 });
+}
+
+#[test]
+#[should_panic(expected = "non-zero")]
+fn lockout_rejects_zero_samples() {
+    button(DebounceAlgorithm::Lockout { samples: 0 });
+}
+
+#[test]
+fn a_single_sample_is_enough() {
+    for debounce in [
+        DebounceAlgorithm::Counting { samples: 1 },
+        DebounceAlgorithm::Asymmetric {
+            press_samples: 1,
+            release_samples: 1,
+        },
+        DebounceAlgorithm::Integrator { max: 1 },
+        DebounceAlgorithm::Lockout { samples: 1 },
+    ] {
+        let mut button = button(debounce);
+        assert_eq!(
+            button.update(0, ButtonState::Pressed),
+            Some(ButtonState::Pressed),
+            "{debounce:?}"
+        );
+        // Lockout ignores the pin for a sample after it changes.
+        button.update(1_000, ButtonState::Pressed);
+        assert_eq!(
+            button.update(2_000, ButtonState::NotPressed),
+            Some(ButtonState::NotPressed),
+            "{debounce:?}"
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use super::{
+    button::{Button, ButtonState, DebounceAlgorithm},
+    ButtonAction,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// When the user presses a button, how long do we wait to see if they press the
+/// other button as well? In microseconds.
+const PRESS_AND_HOLD_TIMEOUT_US: u32 = 100_000; // 100,000 us = 100 ms
+
+/// Turns the raw state of the A and B buttons into actions.
+///
+/// Press A or B to move left or right. Press both at once to fire. Optionally,
+/// keep holding them to keep moving or firing; see `AutoRepeat`.
+///
+/// This doesn't touch the hardware, so it's the same however the pins are read.
+/// Timestamps are in microseconds, and may wrap around.
+pub struct Chords {
+    buttons: [Button; 2],
+    /// If either button is held, this is the timestamp of when it was
+    /// initially pressed. At most one button is held at a time.
+    held_since: Option<u32>,
+    auto_repeat: Option<AutoRepeat>,
+    /// The action to repeat while its buttons are held, if any.
+    repeating: Option<Repeating>,
+    stats: ChordStats,
+}
+
+/// Holding buttons down keeps repeating their action: a held A or B keeps
+/// moving, and holding both keeps firing.
+#[derive(Debug, Clone, Copy)]
+pub struct AutoRepeat {
+    /// How long to hold the buttons after the first action, before it starts
+    /// repeating, in microseconds.
+    delay_us: u32,
+    /// How often `Left` and `Right` repeat, in microseconds.
+    move_period_us: u32,
+    /// How often `Fire` repeats, in microseconds.
+    fire_period_us: u32,
+}
+
+impl AutoRepeat {
+    /// All in microseconds, and none of them can be zero: `Chords` only has
+    /// room for one action per update.
+    pub const fn new(delay_us: u32, move_period_us: u32, fire_period_us: u32) -> Self {
+        assert!(
+            delay_us != 0 && move_period_us != 0 && fire_period_us != 0,
+            "auto-repeat delay and periods must be non-zero"
+        );
+        Self {
+            delay_us,
+            move_period_us,
+            fire_period_us,
+        }
+    }
+}
+
+struct Repeating {
+    action: ButtonAction,
+    /// The timestamp of the last time `action` happened.
+    since: u32,
+    /// How long to wait from `since` until the next time.
+    wait_us: u32,
+}
+
+/// How often the player pressed both buttons, and how often they meant to.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ChordStats {
+    /// Both buttons pressed together, to fire.
+    pub chords: u32,
+    /// One button pressed while the other was still held, but too late to
+    /// count as pressing them together, so the first one moved instead.
+    pub misses: u32,
+}
+
+impl Chords {
+    /// `initial_states` are what the pins read right now.
+    pub fn new(
+        initial_states: [ButtonState; 2],
+        debounce: [DebounceAlgorithm; 2],
+        auto_repeat: Option<AutoRepeat>,
+    ) -> Self {
+        let [a, b] = initial_states;
+        let mut this = Self {
+            buttons: [Button::new(a, debounce[0]), Button::new(b, debounce[1])],
+            held_since: None,
+            auto_repeat,
+            repeating: None,
+            stats: ChordStats::default(),
+        };
+
+        // The `update` logic is simpler if we can assume both buttons start
+        // "not-pressed".
+        for b in &mut this.buttons {
+            if b.state().is_pressed() {
+                b.force_state_change(ButtonState::NotPressed);
+            }
+        }
+
+        this
+    }
+
+    pub fn buttons(&self) -> &[Button; 2] {
+        &self.buttons
+    }
+
+    pub fn stats(&self) -> &ChordStats {
+        &self.stats
+    }
+
+    /// Is there nothing going on, that `update` would need to keep an eye on?
+    /// If so, it won't return anything until one of the pins changes.
+    pub fn is_settled(&self) -> bool {
+        self.held_since.is_none()
+            && self.repeating.is_none()
+            && self.buttons.iter().all(|b| b.settled_state().is_some())
+    }
+
+    /// You must call this every `button::BUTTON_TIMER_US` microseconds, unless
+    /// `is_settled`, passing the current time and what the pins read.
+    pub fn update(&mut self, now_us: u32, states: [ButtonState; 2]) -> Option<ButtonAction> {
+        debug_assert!({
+            let both_pressed = self.buttons.iter().all(|b| b.state().is_pressed());
+            !both_pressed
+        });
+
+        let actions = [
+            self.check_timeout(now_us),
+            self.update_button(0, states[0], now_us),
+            self.update_button(1, states[1], now_us),
+            self.check_repeat(now_us),
+        ];
+
+        debug_assert!({
+            let num_actions = actions.iter().flatten().count();
+            num_actions <= 1
+        });
+
+        actions.into_iter().flatten().next()
+    }
+
+    /// Check if a button has been held down for more than a short moment. If
+    /// so, release the button.
+    ///
+    /// This makes movement feel more responsive. The player probably expects
+    /// actions to happen when they *press* buttons, not when they release them.
+    ///
+    /// Instead of reacting *immediately* on-press, we wait briefly to see if
+    /// the user presses the other button as well. That way we can still detect
+    /// the user pressing both buttons "at the same time".
+    fn check_timeout(&mut self, now_us: u32) -> Option<ButtonAction> {
+        if let Some(t) = self.held_since {
+            if now_us.wrapping_sub(t) >= PRESS_AND_HOLD_TIMEOUT_US {
+                // Release whichever button was held.
+                for i in 0..2 {
+                    if self.buttons[i].state().is_pressed() {
+                        self.buttons[i].force_state_change(ButtonState::NotPressed);
+                        self.held_since = None;
+                        let action = ButtonAction::left_right(i);
+                        self.start_repeating(action, now_us);
+                        return Some(action);
+                    }
+                }
+                debug_assert!(false);
+            }
+        }
+        None
+    }
+
+    /// Check a button for state changes.
+    fn update_button(&mut self, i: usize, state: ButtonState, now_us: u32) -> Option<ButtonAction> {
+        match self.buttons[i].update(now_us, state) {
+            None => None,
+            Some(ButtonState::Pressed) => {
+                // Is the other button also pressed?
+                if self.buttons[1 - i].state().is_pressed() {
+                    // Treat both buttons as released, so the player doesn't
+                    // move they physically release the buttons.
+                    self.buttons[i].force_state_change(ButtonState::NotPressed);
+                    self.buttons[1 - i].force_state_change(ButtonState::NotPressed);
+                    self.held_since = None;
+                    self.stats.chords += 1;
+                    self.start_repeating(ButtonAction::Fire, now_us);
+                    Some(ButtonAction::Fire)
+                } else {
+                    if self.buttons[1 - i].is_held() {
+                        self.stats.misses += 1;
+                    }
+                    self.held_since = Some(now_us);
+                    None
+                }
+            }
+            Some(ButtonState::NotPressed) => {
+                self.held_since = None;
+                Some(ButtonAction::left_right(i))
+            }
+        }
+    }
+
+    /// After `action` happens, keep it happening for as long as its buttons
+    /// stay held, if auto-repeat is on.
+    fn start_repeating(&mut self, action: ButtonAction, now_us: u32) {
+        self.repeating = self.auto_repeat.map(|auto_repeat| Repeating {
+            action,
+            since: now_us,
+            wait_us: auto_repeat.delay_us,
+        });
+    }
+
+    /// Repeat the last action if it's time, or stop repeating it if its
+    /// buttons aren't held any more.
+    ///
+    /// Pressing or releasing the other button while moving stops the
+    /// movement, the same as if auto-repeat were off.
+    fn check_repeat(&mut self, now_us: u32) -> Option<ButtonAction> {
+        let (r, auto_repeat) = self.repeating.as_mut().zip(self.auto_repeat)?;
+        let [a, b] = self.buttons.each_ref().map(Button::is_held);
+        let (still_held, period_us) = match r.action {
+            ButtonAction::Left => (a && !b, auto_repeat.move_period_us),
+            ButtonAction::Right => (!a && b, auto_repeat.move_period_us),
+            ButtonAction::Fire => (a && b, auto_repeat.fire_period_us),
+        };
+
+        if !still_held {
+            self.repeating = None;
+            None
+        } else if now_us.wrapping_sub(r.since) >= r.wait_us {
+            // Count from when it was due, not from now, so the rate doesn't
+            // drift.
+            r.since = r.since.wrapping_add(r.wait_us);
+            r.wait_us = period_us;
+            Some(r.action)
+        } else {
+            None
+        }
+    }
+}
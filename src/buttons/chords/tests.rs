@@ -0,0 +1,194 @@
+use super::{AutoRepeat, Chords};
+use crate::buttons::{
+    button::{ButtonState, DebounceAlgorithm, BUTTON_TIMER_US},
+    ButtonAction,
+};
+
+/// A change to one of the pins: when, in microseconds, which button, and
+/// whether it's now pressed.
+type Edge = (u32, usize, bool);
+
+/// Keep sampling for this long after the last pin change, so any holds and
+/// repeats play out. In microseconds.
+const TAIL_US: u32 = 2_000_000;
+
+const ALGORITHMS: [DebounceAlgorithm; 5] = [
+    DebounceAlgorithm::DEFAULT,
+    DebounceAlgorithm::Counting { samples: 3 },
+    DebounceAlgorithm::Asymmetric {
+        press_samples: 2,
+        release_samples: 8,
+    },
+    DebounceAlgorithm::Integrator { max: 5 },
+    DebounceAlgorithm::Lockout { samples: 10 },
+];
+
+const AUTO_REPEAT: AutoRepeat = AutoRepeat::new(300_000, 150_000, 200_000);
+
+/// The recordings from `tools/debounce-bench`, each of a single button.
+const BENCH_TRACES: [&str; 4] = [
+    include_str!("../../../tools/debounce-bench/traces/bouncy-press.txt"),
+    include_str!("../../../tools/debounce-bench/traces/clean.txt"),
+    include_str!("../../../tools/debounce-bench/traces/glitches.txt"),
+    include_str!("../../../tools/debounce-bench/traces/taps.txt"),
+];
+
+/// Parse one of the bench's traces, as if it were recorded from `button`,
+/// starting `offset_us` later.
+fn bench_trace(text: &str, button: usize, offset_us: u32) -> Vec<Edge> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (time, level) = line.split_once(' ').unwrap();
+            let time_us: u32 = time.parse().unwrap();
+            (time_us + offset_us, button, level == "1")
+        })
+        .collect()
+}
+
+/// A clean press of `button`, from `from_ms` until `to_ms`.
+fn press(button: usize, from_ms: u32, to_ms: u32) -> [Edge; 2] {
+    [
+        (from_ms * 1_000, button, true),
+        (to_ms * 1_000, button, false),
+    ]
+}
+
+/// Put the edges of several traces in order.
+fn merge(traces: &[&[Edge]]) -> Vec<Edge> {
+    let mut trace = traces.concat();
+    trace.sort_by_key(|&(time_us, _, _)| time_us);
+    trace
+}
+
+/// Everything to feed through `Chords`: the bench's traces on either button,
+/// and on both at once, and some presses and holds of both buttons.
+fn traces() -> Vec<Vec<Edge>> {
+    let mut traces = Vec::new();
+    for text in BENCH_TRACES {
+        traces.push(bench_trace(text, 0, 0));
+        traces.push(bench_trace(text, 1, 0));
+        traces.push(merge(&[
+            &bench_trace(text, 0, 0),
+            &bench_trace(text, 1, 5_000),
+        ]));
+    }
+    traces.extend([
+        // Fire.
+        merge(&[&press(0, 10, 300), &press(1, 40, 310)]),
+        // Keep moving.
+        press(0, 10, 1_500).to_vec(),
+        // Keep firing.
+        merge(&[&press(0, 10, 1_500), &press(1, 20, 1_490)]),
+        // Too late to fire, so this moves, and then stops.
+        merge(&[&press(0, 10, 1_000), &press(1, 500, 600)]),
+        // Tapping one button while holding the other.
+        merge(&[
+            &press(1, 10, 2_000),
+            &press(0, 800, 850),
+            &press(0, 1_200, 1_250),
+        ]),
+    ]);
+    traces
+}
+
+/// Sample `trace` every `BUTTON_TIMER_US`, and return every action, along with
+/// when it happened, and how many samples were taken.
+///
+/// With `skip_while_settled`, this skips samples the way the firmware does
+/// without `poll-buttons`: while `is_settled`, it waits until a pin is no longer
+/// in its settled state.
+fn run(
+    trace: &[Edge],
+    algorithm: DebounceAlgorithm,
+    auto_repeat: Option<AutoRepeat>,
+    skip_while_settled: bool,
+) -> (Vec<(u32, ButtonAction)>, u32) {
+    let mut chords = Chords::new([ButtonState::NotPressed; 2], [algorithm; 2], auto_repeat);
+    let mut pins = [ButtonState::NotPressed; 2];
+    let mut actions = Vec::new();
+    let mut num_samples = 0;
+
+    let end_us = trace.last().map_or(0, |&(time_us, _, _)| time_us) + TAIL_US;
+    let mut upcoming = trace.iter().peekable();
+    for now_us in (0..=end_us).step_by(BUTTON_TIMER_US as usize) {
+        while let Some(&(_, button, pressed)) = upcoming.next_if(|e| e.0 <= now_us) {
+            pins[button] = if pressed {
+                ButtonState::Pressed
+            } else {
+                ButtonState::NotPressed
+            };
+        }
+
+        let unchanged = (0..2).all(|i| chords.buttons()[i].settled_state() == Some(pins[i]));
+        if skip_while_settled && chords.is_settled() && unchanged {
+            continue;
+        }
+
+        num_samples += 1;
+        if let Some(action) = chords.update(now_us, pins) {
+            actions.push((now_us, action));
+        }
+    }
+    (actions, num_samples)
+}
+
+#[test]
+fn skipping_samples_while_settled_changes_nothing() {
+    let (mut polled_samples, mut skipped_samples) = (0, 0);
+    for trace in traces() {
+        for algorithm in ALGORITHMS {
+            for auto_repeat in [None, Some(AUTO_REPEAT)] {
+                let (polled, num_polled) = run(&trace, algorithm, auto_repeat, false);
+                let (skipped, num_skipped) = run(&trace, algorithm, auto_repeat, true);
+                assert_eq!(
+                    polled, skipped,
+                    "{algorithm:?}, {auto_repeat:?}, trace: {trace:?}"
+                );
+                polled_samples += num_polled;
+                skipped_samples += num_skipped;
+            }
+        }
+    }
+
+    // Otherwise, the test isn't testing much.
+    assert!(
+        skipped_samples < polled_samples / 2,
+        "{skipped_samples} of {polled_samples} samples taken"
+    );
+}
+
+#[test]
+fn chords_and_repeats_are_recognised() {
+    let trace = merge(&[&press(0, 10, 300), &press(1, 40, 310)]);
+    let (actions, _) = run(&trace, DebounceAlgorithm::DEFAULT, None, true);
+    assert_eq!(
+        actions
+            .iter()
+            .map(|&(_, action)| action)
+            .collect::<Vec<_>>(),
+        [ButtonAction::Fire]
+    );
+
+    let trace = press(0, 10, 1_000);
+    let (actions, _) = run(&trace, DebounceAlgorithm::DEFAULT, Some(AUTO_REPEAT), true);
+    // Once after holding it briefly, then again after the delay, and every
+    // period after that.
+    assert_eq!(actions.len(), 1 + 1 + (1_000 - 10 - 100 - 300) / 150);
+    assert!(actions
+        .iter()
+        .all(|&(_, action)| action == ButtonAction::Left));
+}
+
+#[test]
+#[should_panic(expected = "non-zero")]
+fn auto_repeat_rejects_a_zero_delay() {
+    AutoRepeat::new(0, 150_000, 200_000);
+}
+
+#[test]
+#[should_panic(expected = "non-zero")]
+fn auto_repeat_rejects_a_zero_period() {
+    AutoRepeat::new(300_000, 150_000, 0);
+}
@@ -0,0 +1,38 @@
+//! What the buttons mean, as opposed to how they're read. This doesn't touch
+//! the hardware, so `tools/host-tests` can use it too.
+
+/// What the game does with the buttons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum ButtonAction {
+    Left,
+    Right,
+    Fire,
+}
+
+/// Everything `Buttons` recognises. The same press can lead to several of
+/// these, e.g. `Pressed`, then `LongPress`, then `Released`, then
+/// `Action(Left)`, so bind whichever ones make sense.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum ButtonEvent {
+    Action(ButtonAction),
+    Pressed(ButtonId),
+    Released(ButtonId),
+    DoubleTap(ButtonId),
+    LongPress(ButtonId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum ButtonId {
+    A,
+    B,
+}
+
+impl ButtonAction {
+    pub(super) fn left_right(i: usize) -> Self {
+        match i {
+            0 => Self::Left,
+            1 => Self::Right,
+            _ => panic!("buttons are indexed with 0 or 1, got: {i}"),
+        }
+    }
+}
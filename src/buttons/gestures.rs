@@ -0,0 +1,85 @@
+use super::{ButtonEvent, ButtonId};
+
+#[cfg(test)]
+mod tests;
+
+/// How quickly the player has to do things for them to count as gestures.
+#[derive(Debug, Clone, Copy)]
+pub struct GestureTiming {
+    /// Tap a button, then press it again within this long, for a double tap.
+    /// In microseconds.
+    pub double_tap_us: u32,
+    /// Hold a button this long for a long press. In microseconds.
+    pub long_press_us: u32,
+}
+
+/// Recognises gestures on one button, from when it's physically pressed and
+/// released. This is separate from `Chords`, so a gesture doesn't stop the
+/// button from moving the player as well.
+///
+/// Timestamps are in microseconds, and may wrap around.
+pub struct Gestures {
+    button: ButtonId,
+    timing: GestureTiming,
+    held: bool,
+    /// When the button was pressed, while it's held but not for long enough to
+    /// be a long press yet.
+    pressed_at: Option<u32>,
+    /// When the button was last tapped, if it was a single, short tap.
+    tapped_at: Option<u32>,
+    /// Whether the current press is the second half of a double tap.
+    double_tapped: bool,
+}
+
+impl Gestures {
+    pub fn new(button: ButtonId, timing: GestureTiming) -> Self {
+        Self {
+            button,
+            timing,
+            held: false,
+            pressed_at: None,
+            tapped_at: None,
+            double_tapped: false,
+        }
+    }
+
+    /// Is a long press still on its way? If so, `update` needs calling
+    /// regularly to notice it.
+    pub fn is_settled(&self) -> bool {
+        self.pressed_at.is_none()
+    }
+
+    /// Call this whenever the button is updated, with whether it's physically
+    /// held. A double tap comes along with the `Pressed` that completes it.
+    pub fn update(&mut self, now_us: u32, held: bool) -> [Option<ButtonEvent>; 2] {
+        let button = self.button;
+        if held == self.held {
+            return match self.pressed_at {
+                Some(t) if now_us.wrapping_sub(t) >= self.timing.long_press_us => {
+                    self.pressed_at = None;
+                    [Some(ButtonEvent::LongPress(button)), None]
+                }
+                _ => [None, None],
+            };
+        }
+
+        self.held = held;
+        if held {
+            self.pressed_at = Some(now_us);
+            self.double_tapped = self
+                .tapped_at
+                .take()
+                .is_some_and(|t| now_us.wrapping_sub(t) < self.timing.double_tap_us);
+            [
+                Some(ButtonEvent::Pressed(button)),
+                self.double_tapped.then_some(ButtonEvent::DoubleTap(button)),
+            ]
+        } else {
+            // A long press, or the end of a double tap, can't start another
+            // double tap.
+            let was_short = self.pressed_at.take().is_some();
+            self.tapped_at = (was_short && !self.double_tapped).then_some(now_us);
+            [Some(ButtonEvent::Released(button)), None]
+        }
+    }
+}
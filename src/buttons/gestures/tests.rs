@@ -0,0 +1,151 @@
+use super::{GestureTiming, Gestures};
+use crate::buttons::{
+    ButtonEvent::{self, DoubleTap, LongPress, Pressed, Released},
+    ButtonId::A,
+};
+
+const TIMING: GestureTiming = GestureTiming {
+    double_tap_us: 300_000,
+    long_press_us: 1_000_000,
+};
+
+fn new_gestures() -> Gestures {
+    Gestures::new(A, TIMING)
+}
+
+fn update(gestures: &mut Gestures, now_us: u32, held: bool) -> Vec<ButtonEvent> {
+    gestures
+        .update(now_us, held)
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Hold the button from `from_us` until `to_us`, checking every millisecond in
+/// between, and return everything that happened.
+fn hold(gestures: &mut Gestures, from_us: u32, to_us: u32) -> Vec<ButtonEvent> {
+    let mut events = update(gestures, from_us, true);
+    let mut now_us = from_us;
+    while to_us.wrapping_sub(now_us) > 1_000 {
+        now_us = now_us.wrapping_add(1_000);
+        events.extend(update(gestures, now_us, true));
+    }
+    events.extend(update(gestures, to_us, false));
+    events
+}
+
+#[test]
+fn a_single_tap() {
+    let mut gestures = new_gestures();
+    assert_eq!(hold(&mut gestures, 0, 100_000), [Pressed(A), Released(A)]);
+    assert!(gestures.is_settled());
+
+    // Nothing more, however long it's left.
+    for now_us in (100_000..2_000_000).step_by(1_000) {
+        assert_eq!(update(&mut gestures, now_us, false), []);
+    }
+}
+
+#[test]
+fn a_double_tap_inside_double_tap_us() {
+    let mut gestures = new_gestures();
+    hold(&mut gestures, 0, 100_000);
+    let second_us = 100_000 + TIMING.double_tap_us - 1;
+    assert_eq!(
+        update(&mut gestures, second_us, true),
+        [Pressed(A), DoubleTap(A)]
+    );
+    assert_eq!(
+        update(&mut gestures, second_us + 50_000, false),
+        [Released(A)]
+    );
+
+    // The second tap can't start another double tap.
+    assert_eq!(
+        update(&mut gestures, second_us + 100_000, true),
+        [Pressed(A)]
+    );
+}
+
+#[test]
+fn no_double_tap_just_outside_double_tap_us() {
+    let mut gestures = new_gestures();
+    hold(&mut gestures, 0, 100_000);
+    let second_us = 100_000 + TIMING.double_tap_us;
+    assert_eq!(update(&mut gestures, second_us, true), [Pressed(A)]);
+}
+
+#[test]
+fn a_long_press_fires_once() {
+    let mut gestures = new_gestures();
+    assert_eq!(update(&mut gestures, 0, true), [Pressed(A)]);
+    assert!(!gestures.is_settled());
+    assert_eq!(update(&mut gestures, TIMING.long_press_us - 1, true), []);
+    assert_eq!(
+        update(&mut gestures, TIMING.long_press_us, true),
+        [LongPress(A)]
+    );
+    assert!(gestures.is_settled());
+
+    // Not again, however long it's held.
+    for now_us in (TIMING.long_press_us..5_000_000).step_by(1_000) {
+        assert_eq!(update(&mut gestures, now_us, true), []);
+    }
+    assert_eq!(update(&mut gestures, 5_000_000, false), [Released(A)]);
+
+    // A long press can't start a double tap.
+    assert_eq!(update(&mut gestures, 5_050_000, true), [Pressed(A)]);
+}
+
+#[test]
+fn presses_and_releases_pair_up() {
+    let mut gestures = new_gestures();
+    let mut held = false;
+    let mut events = Vec::new();
+    // Press and release after all sorts of delays, reporting each state a few
+    // times over.
+    let mut now_us = 0u32;
+    for i in 0..200u32 {
+        now_us += 1_000 + i * 7_919 % 1_500_000;
+        held = !held;
+        for _ in 0..3 {
+            events.extend(update(&mut gestures, now_us, held));
+            now_us += 1_000;
+        }
+    }
+
+    let presses_and_releases: Vec<ButtonEvent> = events
+        .into_iter()
+        .filter(|e| matches!(e, Pressed(_) | Released(_)))
+        .collect();
+    assert_eq!(presses_and_releases.len(), 200);
+    for pair in presses_and_releases.chunks(2) {
+        assert_eq!(pair, [Pressed(A), Released(A)]);
+    }
+}
+
+#[test]
+fn copes_with_now_us_wrapping_around() {
+    // A long press that spans the wrap.
+    let mut gestures = new_gestures();
+    let start_us = u32::MAX - 500_000;
+    assert_eq!(update(&mut gestures, start_us, true), [Pressed(A)]);
+    let almost_us = start_us.wrapping_add(TIMING.long_press_us - 1);
+    assert_eq!(update(&mut gestures, almost_us, true), []);
+    let long_us = start_us.wrapping_add(TIMING.long_press_us);
+    assert_eq!(update(&mut gestures, long_us, true), [LongPress(A)]);
+    assert_eq!(update(&mut gestures, long_us + 1_000, false), [Released(A)]);
+
+    // A short tap that spans it, then a double tap.
+    let mut gestures = new_gestures();
+    let start_us = u32::MAX - 50_000;
+    let release_us = start_us.wrapping_add(100_000);
+    assert_eq!(
+        hold(&mut gestures, start_us, release_us),
+        [Pressed(A), Released(A)]
+    );
+    assert_eq!(
+        update(&mut gestures, release_us + 200_000, true),
+        [Pressed(A), DoubleTap(A)]
+    );
+}
@@ -8,6 +8,8 @@ use nrf52833_hal::{
 };
 use void::ResultVoidExt;
 
+use crate::log::log;
+
 /// The display is a square grid with this many rows and columns.
 pub const DISPLAY_SIZE: i8 = 5;
 
@@ -94,7 +96,11 @@ impl Display {
         self.curr_row %= DISPLAY_SIZE;
 
         if self.curr_row == 0 {
+            let previous = self.display_buffer;
             update_display_buffer(&mut self.display_buffer);
+            if self.display_buffer != previous {
+                log!(display, trace, "frame {}", self.display_buffer);
+            }
         }
 
         for col in 0..DISPLAY_SIZE {
@@ -109,4 +115,11 @@ impl Display {
         // Display the current row.
         self.rows[self.curr_row as usize].set_high().void_unwrap();
     }
+
+    /// Turn off every LED, e.g. before going to sleep. They stay off until
+    /// `update` is called again.
+    pub fn clear(&mut self) {
+        self.rows[self.curr_row as usize].set_low().void_unwrap();
+        self.display_buffer = BoolGrid::default();
+    }
 }
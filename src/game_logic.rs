@@ -1,22 +1,50 @@
+pub use self::config::{ConfigError, GameConfig};
 use self::{
-    loss_animation::LossAnimation, playing::Playing, start_animation::StartAnimation,
-    win_animation::WinAnimation,
+    ai::Ai, boss_victory_animation::BossVictoryAnimation, loss_animation::LossAnimation,
+    playing::Playing, recording::Recording, rng::Rng, start_animation::StartAnimation,
+    transition::Transition, win_animation::WinAnimation,
 };
-use crate::{buttons::ButtonAction, display::BoolGrid};
+use crate::{buttons::ButtonAction, display::BoolGrid, log::log};
 
+pub mod ai;
+mod animation;
+mod boss_victory_animation;
+mod config;
+mod enemy;
+mod interval;
+mod levels;
 mod loss_animation;
 mod playing;
+pub mod recording;
+mod rng;
 mod start_animation;
+mod transition;
 mod win_animation;
 
 /// How often to call Game::update, in microseconds.
-//
-// The code below depends on this being 1 millisecond.
 pub const GAME_UPDATE_TIMER_US: u32 = 1_000;
 
+/// How much time passes during each `Game::update`, in milliseconds.
+const UPDATE_MS: u32 = GAME_UPDATE_TIMER_US / 1_000;
+
+/// If nobody has pressed a button for this long, the next game is played by the
+/// computer, to attract passers-by. In milliseconds.
+const ATTRACT_MODE_IDLE_MS: u32 = 30_000;
+
 pub struct Game {
+    config: &'static GameConfig,
     phase: Phase,
-    num_updates: u32,
+    /// All of the game's randomness comes from here, so that a game can be
+    /// reproduced from its seed and the player's actions.
+    rng: Rng,
+    /// The player's actions since the current `StartAnimation` began.
+    recording: Recording,
+    /// How long since the player last pressed a button, in milliseconds.
+    idle_ms: u32,
+    /// In attract mode, this plays the game instead of the player.
+    ai: Option<Ai>,
+    /// Set while the screen changes over from the previous phase.
+    transition: Option<Transition>,
 }
 
 enum Phase {
@@ -24,61 +52,164 @@ enum Phase {
     Playing(Playing),
     LossAnimation(LossAnimation),
     WinAnimation(WinAnimation),
+    BossVictoryAnimation(BossVictoryAnimation),
 }
 
 /// Common functionality of various phases of the game.
 trait GamePhase {
     fn display(&self, display_buffer: &mut BoolGrid);
 
-    /// How often to call `update`, in milliseconds. Must be non-zero.
-    fn update_timer_ms(&self) -> u32;
-
-    fn update(&mut self) -> Option<Phase>;
+    /// Advance the phase by `elapsed_ms` milliseconds. Returns the next phase,
+    /// if it's time to switch.
+    fn update(&mut self, elapsed_ms: u32, rng: &mut Rng) -> Option<Phase>;
 }
 
 impl Game {
-    pub fn new() -> Self {
-        Self {
-            phase: Phase::StartAnimation(StartAnimation::new()),
-            num_updates: 0,
-        }
+    /// Two games created with the same config and seed behave identically,
+    /// given the same player actions.
+    pub fn new(config: &'static GameConfig, seed: u32) -> Result<Self, ConfigError> {
+        config.validate()?;
+
+        let rng = Rng::new(seed);
+        Ok(Self {
+            config,
+            phase: Phase::StartAnimation(StartAnimation::new(config)),
+            recording: Recording::new(config, rng.seed(), 0, None),
+            rng,
+            idle_ms: 0,
+            ai: None,
+            transition: None,
+        })
     }
 
     pub fn display(&self, display_buffer: &mut BoolGrid) {
-        self.game_phase().display(display_buffer);
+        self.phase.game_phase().display(display_buffer);
+        if let Some(transition) = &self.transition {
+            transition.display(display_buffer);
+        }
     }
 
     pub fn player_action(&mut self, action: ButtonAction) {
+        self.recording.record_action(action);
+        self.idle_ms = 0;
+
+        // Someone wants to play! Stop the demo game and start a real one.
+        if self.ai.is_some() {
+            log!(game, info, "leaving attract mode");
+            self.restart();
+            return;
+        }
+
         if let Phase::Playing(p) = &mut self.phase {
-            p.player_action(action);
+            p.player_action(action, &mut self.rng);
         }
     }
 
     pub fn update(&mut self) {
-        if self.num_updates % self.game_phase().update_timer_ms() == 0 {
-            if let Some(new_phase) = self.game_phase_mut().update() {
-                self.phase = new_phase;
-                self.num_updates = 0;
+        if let Some(mut ai) = self.ai.take() {
+            let action = ai.update(self);
+            if let (Some(action), Phase::Playing(p)) = (action, &mut self.phase) {
+                p.player_action(action, &mut self.rng);
             }
+            self.ai = Some(ai);
+        }
+
+        let mut new_session = false;
+        let phase = self.phase.game_phase_mut();
+        if let Some(new_phase) = phase.update(UPDATE_MS, &mut self.rng) {
+            new_session = matches!(new_phase, Phase::StartAnimation(_));
+            self.set_phase(new_phase);
+        }
+        if let Some(transition) = &mut self.transition {
+            if transition.tick(UPDATE_MS) {
+                self.transition = None;
+            }
+        }
+        self.idle_ms = self.idle_ms.saturating_add(UPDATE_MS);
+
+        let mut frame = BoolGrid::default();
+        self.display(&mut frame);
+        self.recording.record_tick(&frame);
+
+        if new_session {
+            self.new_session();
         }
-        self.num_updates += 1;
     }
 
+    /// Abandon the current game, and go back to the start animation.
+    pub fn restart(&mut self) {
+        self.set_phase(Phase::StartAnimation(StartAnimation::new(self.config)));
+        self.new_session();
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    /// Is the computer playing a demo game?
+    pub fn in_attract_mode(&self) -> bool {
+        self.ai.is_some()
+    }
+
+    fn set_phase(&mut self, new_phase: Phase) {
+        if let Some(effect) = self.config.transition {
+            // Start from whatever's on screen, even if that's part way
+            // through another transition.
+            let mut from = BoolGrid::default();
+            self.display(&mut from);
+            self.transition = Some(Transition::new(effect, self.config.transition_ms, from));
+        }
+
+        self.phase = new_phase;
+
+        match self.phase {
+            Phase::StartAnimation(_) => {
+                self.ai = None;
+            }
+            // Only start a demo from the first wave; someone who stops
+            // pressing buttons mid-game is still playing.
+            Phase::Playing(ref p) if p.wave == 0 && self.idle_ms >= ATTRACT_MODE_IDLE_MS => {
+                log!(game, info, "entering attract mode");
+                self.ai = Some(Ai::new());
+            }
+            _ => {}
+        }
+    }
+
+    /// Start recording from scratch.
+    ///
+    /// Call this between updates, right after switching to a new
+    /// `StartAnimation`. At that point, the game is in the same state as one
+    /// created by `Game::new`, apart from the seed, idle time and transition.
+    fn new_session(&mut self) {
+        self.recording.dump();
+        self.recording = Recording::new(
+            self.config,
+            self.rng.seed(),
+            self.idle_ms,
+            self.transition.clone(),
+        );
+    }
+}
+
+impl Phase {
     fn game_phase(&self) -> &dyn GamePhase {
-        match &self.phase {
+        match self {
             Phase::StartAnimation(s) => s,
             Phase::Playing(p) => p,
             Phase::LossAnimation(l) => l,
             Phase::WinAnimation(w) => w,
+            Phase::BossVictoryAnimation(b) => b,
         }
     }
 
     fn game_phase_mut(&mut self) -> &mut dyn GamePhase {
-        match &mut self.phase {
+        match self {
             Phase::StartAnimation(s) => s,
             Phase::Playing(p) => p,
             Phase::LossAnimation(l) => l,
             Phase::WinAnimation(w) => w,
+            Phase::BossVictoryAnimation(b) => b,
         }
     }
 }
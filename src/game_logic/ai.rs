@@ -0,0 +1,103 @@
+use super::{Game, Phase};
+use crate::{buttons::ButtonAction, display::DISPLAY_SIZE};
+
+/// How often the AI presses a button, in milliseconds. This is roughly as fast
+/// as a person can, so the demo looks like someone is actually playing.
+const ACTION_INTERVAL_MS: u32 = 200;
+
+/// A computer player. It dodges enemy bullets, moves under the enemy closest to
+/// the bottom of the screen, and fires.
+///
+/// `Game` uses this to play a demo game in attract mode. It can also be used to
+/// soak-test the game: call `update` once per `Game::update`, and pass any
+/// actions to `Game::player_action`.
+#[derive(Debug, Clone)]
+pub struct Ai {
+    ms_until_action: u32,
+}
+
+impl Ai {
+    pub fn new() -> Self {
+        Self {
+            ms_until_action: ACTION_INTERVAL_MS,
+        }
+    }
+
+    /// Decide what to do next, if anything.
+    ///
+    /// You must call this every millisecond, along with `Game::update`.
+    pub fn update(&mut self, game: &Game) -> Option<ButtonAction> {
+        let Phase::Playing(playing) = &game.phase else {
+            return None;
+        };
+
+        self.ms_until_action -= 1;
+        if self.ms_until_action != 0 {
+            return None;
+        }
+        self.ms_until_action = ACTION_INTERVAL_MS;
+
+        let bottom = DISPLAY_SIZE as usize - 1;
+        let x = playing.player_x;
+
+        // Is a bullet or a diver about to land on us? Step out of the way,
+        // towards the middle of the screen.
+        let in_danger = |col: i8| {
+            let bullet = (bottom - 2..=bottom).any(|row| playing.enemy_bullets[row][col as usize]);
+            let diver = playing
+                .divers
+                .iter()
+                .flatten()
+                .any(|diver| diver.col == col && diver.row as usize >= bottom - 2);
+            bullet || diver
+        };
+        if in_danger(x) {
+            let away = if x < DISPLAY_SIZE / 2 { 1 } else { -1 };
+            for dx in [away, -away] {
+                let col = x + dx;
+                if (0..DISPLAY_SIZE).contains(&col) && !in_danger(col) {
+                    return Some(step(dx));
+                }
+            }
+            return None;
+        }
+
+        // Aim for the lowest enemy, breaking ties by distance. Our own shields
+        // are in the way of some columns.
+        let boss = playing
+            .boss
+            .as_ref()
+            .map(|boss| boss.grid())
+            .unwrap_or_default();
+        let is_enemy =
+            |row: usize, col: usize| playing.enemies[row][col].is_some() || boss[row][col];
+        let target = (0..DISPLAY_SIZE)
+            .filter(|&col| playing.shields[col as usize] == 0)
+            .filter_map(|col| {
+                let lowest = (0..bottom).rev().find(|&row| is_enemy(row, col as usize))?;
+                Some((lowest, -(col - x).abs(), col))
+            })
+            .max()
+            .map(|(_, _, col)| col)?;
+
+        if target != x {
+            return Some(step(target - x));
+        }
+
+        // Only one bullet per column; otherwise we'd fire at every chance.
+        let already_fired = (0..bottom).any(|row| playing.bullets[row][x as usize]);
+        if already_fired {
+            None
+        } else {
+            Some(ButtonAction::Fire)
+        }
+    }
+}
+
+fn step(dx: i8) -> ButtonAction {
+    if dx < 0 {
+        ButtonAction::Left
+    } else {
+        ButtonAction::Right
+    }
+}
@@ -0,0 +1,171 @@
+use crate::display::{BoolGrid, DISPLAY_SIZE};
+
+/// A picture for an effect to work on.
+#[derive(Debug, Clone, Copy)]
+pub enum Picture {
+    Blank,
+    /// Known in advance.
+    Fixed(BoolGrid),
+    /// Whatever was on screen when the animation started. See
+    /// `Animation::with_scene`.
+    Scene,
+    /// Part of the scene to draw attention to, e.g. whatever hit the player.
+    /// See `Animation::with_highlight`.
+    Highlight,
+}
+
+/// What to show during a keyframe. `step` counts from 0 at the start of the
+/// keyframe.
+#[derive(Debug, Clone, Copy)]
+pub enum Effect {
+    /// A still picture.
+    Show(Picture),
+    /// Show `picture`, with `blink` turning on and off every step, starting on.
+    Blink { picture: Picture, blink: Picture },
+    /// A diagonal line, two pixels wide, sweeping from the top-left corner to
+    /// the bottom-right every 10 steps.
+    Sweep,
+    /// Square rings spreading out from the origin, one pixel per 2 steps. See
+    /// `Animation::with_origin`.
+    Ripple,
+}
+
+/// One part of an animation.
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe {
+    /// How long this part lasts, in animation steps.
+    pub steps: u32,
+    pub effect: Effect,
+}
+
+/// Plays a sequence of keyframes, one step at a time.
+///
+/// The keyframes themselves are `const` data; anything that depends on the
+/// game, like the scene being animated, is passed in when the animation starts.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    keyframes: &'static [Keyframe],
+    step_ms: u32,
+    /// How many steps have passed since the animation started.
+    step: u32,
+    /// How long the current step has lasted so far, in milliseconds.
+    step_elapsed_ms: u32,
+    scene: BoolGrid,
+    highlight: BoolGrid,
+    origin: (i8, i8),
+}
+
+impl Animation {
+    /// Each step lasts `step_ms` milliseconds, which must be non-zero.
+    pub fn new(keyframes: &'static [Keyframe], step_ms: u32) -> Self {
+        Self {
+            keyframes,
+            step_ms,
+            step: 0,
+            step_elapsed_ms: 0,
+            scene: BoolGrid::default(),
+            highlight: BoolGrid::default(),
+            origin: (DISPLAY_SIZE / 2, DISPLAY_SIZE / 2),
+        }
+    }
+
+    pub fn with_scene(self, scene: BoolGrid) -> Self {
+        Self { scene, ..self }
+    }
+
+    pub fn with_highlight(self, highlight: BoolGrid) -> Self {
+        Self { highlight, ..self }
+    }
+
+    pub fn with_origin(self, origin: (i8, i8)) -> Self {
+        Self { origin, ..self }
+    }
+
+    /// Advance by `elapsed_ms`, moving on to later steps as they come due.
+    /// Returns true once the animation is over.
+    pub fn update(&mut self, elapsed_ms: u32) -> bool {
+        self.step_elapsed_ms += elapsed_ms;
+        self.step += self.step_elapsed_ms / self.step_ms;
+        self.step_elapsed_ms %= self.step_ms;
+        self.step >= self.keyframes.iter().map(|k| k.steps).sum::<u32>()
+    }
+
+    pub fn display(&self, display_buffer: &mut BoolGrid) {
+        *display_buffer = BoolGrid::default();
+
+        // Find the current keyframe, and how far into it we are.
+        let mut step = self.step;
+        for keyframe in self.keyframes {
+            if step < keyframe.steps {
+                self.draw(keyframe, step, display_buffer);
+                return;
+            }
+            step -= keyframe.steps;
+        }
+    }
+
+    fn draw(&self, keyframe: &Keyframe, step: u32, display_buffer: &mut BoolGrid) {
+        match keyframe.effect {
+            Effect::Show(picture) => {
+                *display_buffer = self.picture(picture);
+            }
+            Effect::Blink { picture, blink } => {
+                *display_buffer = self.picture(picture);
+                let blink = self.picture(blink);
+                let on = step.is_multiple_of(2);
+                for_each_pixel(display_buffer, |row, col, pixel| {
+                    if blink[row][col] {
+                        *pixel = on;
+                    }
+                });
+            }
+            Effect::Sweep => {
+                let tick = step as usize % 10;
+                for_each_pixel(display_buffer, |row, col, pixel| {
+                    let sum = row + col;
+                    let leading_edge = sum == tick;
+                    let trailing_edge = sum + 1 == tick;
+                    *pixel = leading_edge || trailing_edge;
+                });
+            }
+            Effect::Ripple => {
+                let (origin_row, origin_col) = self.origin;
+                let radius = (step / 2 % DISPLAY_SIZE as u32) as i8;
+                for_each_pixel(display_buffer, |row, col, pixel| {
+                    let drow = (row as i8 - origin_row).abs();
+                    let dcol = (col as i8 - origin_col).abs();
+                    *pixel = drow.max(dcol) == radius;
+                });
+            }
+        }
+    }
+
+    fn picture(&self, picture: Picture) -> BoolGrid {
+        match picture {
+            Picture::Blank => BoolGrid::default(),
+            Picture::Fixed(grid) => grid,
+            Picture::Scene => self.scene,
+            Picture::Highlight => self.highlight,
+        }
+    }
+}
+
+pub const NUM_PIXELS: u32 = (DISPLAY_SIZE * DISPLAY_SIZE) as u32;
+
+/// Gives each pixel a different number, from 0 to `NUM_PIXELS - 1`, in an order
+/// that jumps around the screen. Handy for making pixels change one by one,
+/// without it looking like a sweep.
+//
+// Multiplying by 7 scatters them, since 7 and 25 have no common factors.
+pub fn scattered_order(row: usize, col: usize) -> u32 {
+    let index = (row * DISPLAY_SIZE as usize + col) as u32;
+    index * 7 % NUM_PIXELS
+}
+
+pub fn for_each_pixel(grid: &mut BoolGrid, mut f: impl FnMut(usize, usize, &mut bool)) {
+    for (row, pixels) in grid.iter_mut().enumerate() {
+        for (col, pixel) in pixels.iter_mut().enumerate() {
+            f(row, col, pixel);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use super::{
+    animation::{Animation, Effect, Keyframe, Picture},
+    playing::Playing,
+    rng::Rng,
+    start_animation::StartAnimation,
+    GamePhase, Phase,
+};
+use crate::display::BoolGrid;
+
+const KEYFRAMES: &[Keyframe] = &[
+    // The boss flickers for a moment, as it's defeated.
+    Keyframe {
+        steps: 10,
+        effect: Effect::Blink {
+            picture: Picture::Scene,
+            blink: Picture::Highlight,
+        },
+    },
+    // Then explodes: 3 rings spread out from where it was.
+    Keyframe {
+        steps: 30,
+        effect: Effect::Ripple,
+    },
+    // Followed by a few flashes of the whole screen.
+    Keyframe {
+        steps: 10,
+        effect: Effect::Blink {
+            picture: Picture::Blank,
+            blink: Picture::Fixed([[true; 5]; 5]),
+        },
+    },
+    Keyframe {
+        steps: 10,
+        effect: Effect::Show(Picture::Blank),
+    },
+];
+
+pub struct BossVictoryAnimation {
+    game_state: Playing,
+    animation: Animation,
+}
+
+impl BossVictoryAnimation {
+    pub fn new(game_state: Playing) -> Self {
+        let mut scene = BoolGrid::default();
+        game_state.display(&mut scene);
+        let mut animation =
+            Animation::new(KEYFRAMES, game_state.config.boss_victory_animation_step_ms)
+                .with_scene(scene);
+        if let Some(boss) = &game_state.boss {
+            animation = animation
+                .with_highlight(boss.grid())
+                .with_origin(boss.centre());
+        }
+        Self {
+            game_state,
+            animation,
+        }
+    }
+}
+
+impl GamePhase for BossVictoryAnimation {
+    fn display(&self, display_buffer: &mut BoolGrid) {
+        self.animation.display(display_buffer);
+    }
+
+    fn update(&mut self, elapsed_ms: u32, rng: &mut Rng) -> Option<Phase> {
+        if !self.animation.update(elapsed_ms) {
+            None
+        } else if let Some(next_wave) = self.game_state.next_wave(rng) {
+            Some(Phase::Playing(next_wave))
+        } else {
+            Some(Phase::StartAnimation(StartAnimation::new(
+                self.game_state.config,
+            )))
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use super::{enemy::Formation, levels};
+use crate::display::DISPLAY_SIZE;
+
+/// The rules of the game, and how fast everything happens.
+#[derive(Debug, Clone)]
+pub struct GameConfig {
+    /// How often the player's bullets move one row, in milliseconds.
+    pub bullet_period_ms: u32,
+    /// How often the enemy formation moves one step, in milliseconds, at the
+    /// start of the game.
+    pub march_period_ms: u32,
+    /// The march speeds up as enemies are destroyed. This is how often the
+    /// last enemy standing moves, in milliseconds.
+    pub min_march_period_ms: u32,
+    /// How often enemy bullets move one row, in milliseconds.
+    pub enemy_bullet_period_ms: u32,
+    /// How often the enemies consider firing, in milliseconds.
+    pub enemy_fire_period_ms: u32,
+    /// Each time the enemies consider firing, there's a 1 in this many chance
+    /// that one of them does.
+    pub enemy_fire_chance: u32,
+    /// Win a wave by destroying all the enemies, then the next one begins.
+    pub waves: &'static [Wave],
+    /// A fight against a single, big enemy, part way through the waves.
+    pub boss: Option<BossWave>,
+    /// About how often the mystery ship flies across the top row, in
+    /// milliseconds. The actual delay is random, from half this long to one and
+    /// a half times this long.
+    pub mystery_ship_period_ms: u32,
+    /// How often the mystery ship moves one step, in milliseconds.
+    pub mystery_ship_step_ms: u32,
+    /// How many of the player's bullets can be on screen at once.
+    pub max_bullets: u8,
+    /// After firing, the player must wait this long before firing again, in
+    /// milliseconds.
+    pub fire_cooldown_ms: u32,
+    /// Each destroyed enemy has a 1 in this many chance of dropping a
+    /// power-up.
+    pub power_up_chance: u32,
+    /// How often falling power-ups move one row, in milliseconds.
+    pub power_up_fall_ms: u32,
+    /// How long a power-up lasts once caught, in milliseconds.
+    pub power_up_duration_ms: u32,
+    /// In waves with divers, how often an enemy leaves the formation to dive
+    /// at the player, in milliseconds.
+    pub dive_period_ms: u32,
+    /// How often a diving enemy moves one step, in milliseconds.
+    pub dive_step_ms: u32,
+    /// How many times the player can be shot before losing. Enemies reaching
+    /// the bottom row is always game over.
+    pub lives: u8,
+    /// How long each step of `StartAnimation` lasts, in milliseconds.
+    pub start_animation_step_ms: u32,
+    /// How long each step of `WinAnimation` lasts, in milliseconds.
+    pub win_animation_step_ms: u32,
+    /// How long each step of `LossAnimation` lasts, in milliseconds.
+    pub loss_animation_step_ms: u32,
+    /// How long each step of `BossVictoryAnimation` lasts, in milliseconds.
+    pub boss_victory_animation_step_ms: u32,
+    /// How to get from one phase of the game to the next, e.g. from the start
+    /// animation to playing. `None` cuts straight to the next phase.
+    pub transition: Option<TransitionEffect>,
+    /// How long a transition lasts, in milliseconds.
+    pub transition_ms: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Wave {
+    /// The possible starting formations; one is picked at random.
+    pub formations: &'static [Formation],
+    /// Which columns have a shield, in the row just above the player.
+    pub shields: [bool; DISPLAY_SIZE as usize],
+    /// How the formation moves.
+    pub movement: Movement,
+    /// How fast the formation marches, as a percentage of the config's usual
+    /// speed.
+    pub march_speed_percent: u32,
+    /// Whether enemies sometimes leave the formation, and dive at the player.
+    pub divers: bool,
+}
+
+/// The ways an enemy formation can move. They all turn around, or move down
+/// instead, when the formation reaches the edge of the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Movement {
+    /// Right, down, left, down, and repeat.
+    Shuffle,
+    /// Sideways until reaching an edge, then down, and back the other way.
+    March,
+    /// Like `March`, but moving down after every step sideways.
+    Zigzag,
+}
+
+/// The ways the screen can change over from one phase to the next. While it
+/// does, the next phase is already running.
+///
+/// There's no fading, since each LED is either on or off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionEffect {
+    /// The next phase comes in from the left, a column at a time.
+    Wipe,
+    /// The next phase appears a pixel at a time, all over the screen.
+    Dissolve,
+    /// The next phase appears in the middle, and spreads out to the edges.
+    Iris,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BossWave {
+    /// The boss appears once the player has cleared this many waves. Any
+    /// waves after that follow once the boss is defeated.
+    pub after_waves: usize,
+    /// How many hits it takes to defeat the boss.
+    pub hp: u8,
+    /// How often the boss moves one step, in milliseconds.
+    pub step_ms: u32,
+    /// How often the boss fires, in milliseconds.
+    pub fire_period_ms: u32,
+    /// Which columns have a shield, in the row just above the player.
+    pub shields: [bool; DISPLAY_SIZE as usize],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum ConfigError {
+    /// A period, step length, chance, or bullet limit was zero.
+    Zero,
+    /// `min_march_period_ms` is greater than `march_period_ms`.
+    MarchPeriod,
+    NoWaves,
+    NoFormations {
+        wave: usize,
+    },
+    /// This formation has no enemies.
+    EmptyFormation {
+        wave: usize,
+        formation: usize,
+    },
+    /// This formation has an enemy in the bottom row, so the player would lose
+    /// immediately.
+    FormationTooLow {
+        wave: usize,
+        formation: usize,
+    },
+    NoLives,
+    /// The boss would appear before the first wave, or after the last.
+    BossWave,
+}
+
+const NO_SHIELDS: [bool; DISPLAY_SIZE as usize] = [false; DISPLAY_SIZE as usize];
+const SHIELDS: [bool; DISPLAY_SIZE as usize] = [false, true, false, true, false];
+
+impl GameConfig {
+    pub const EASY: Self = Self {
+        march_period_ms: 1_200,
+        min_march_period_ms: 600,
+        enemy_bullet_period_ms: 400,
+        enemy_fire_period_ms: 1_200,
+        enemy_fire_chance: 5,
+        mystery_ship_period_ms: 15_000,
+        mystery_ship_step_ms: 150,
+        max_bullets: 3,
+        fire_cooldown_ms: 200,
+        power_up_chance: 3,
+        power_up_duration_ms: 10_000,
+        dive_period_ms: 4_000,
+        dive_step_ms: 300,
+        waves: &[levels::TRAINING, levels::CHECKERS],
+        boss: Some(BossWave {
+            after_waves: 2,
+            hp: 6,
+            step_ms: 600,
+            fire_period_ms: 2_500,
+            shields: SHIELDS,
+        }),
+        lives: 3,
+        transition: Some(TransitionEffect::Iris),
+        transition_ms: 400,
+        ..Self::NORMAL
+    };
+
+    pub const NORMAL: Self = Self {
+        bullet_period_ms: 150,
+        march_period_ms: 1_000,
+        min_march_period_ms: 400,
+        enemy_bullet_period_ms: 300,
+        enemy_fire_period_ms: 1_000,
+        enemy_fire_chance: 3,
+        mystery_ship_period_ms: 12_000,
+        mystery_ship_step_ms: 120,
+        max_bullets: 2,
+        fire_cooldown_ms: 300,
+        power_up_chance: 5,
+        power_up_fall_ms: 300,
+        power_up_duration_ms: 8_000,
+        dive_period_ms: 3_000,
+        dive_step_ms: 250,
+        waves: &[levels::INVASION, levels::VANGUARD, levels::SWARM],
+        boss: Some(BossWave {
+            after_waves: 3,
+            hp: 10,
+            step_ms: 500,
+            fire_period_ms: 2_000,
+            shields: SHIELDS,
+        }),
+        lives: 2,
+        start_animation_step_ms: 250,
+        win_animation_step_ms: 50,
+        loss_animation_step_ms: 500,
+        boss_victory_animation_step_ms: 80,
+        transition: Some(TransitionEffect::Dissolve),
+        transition_ms: 300,
+    };
+
+    pub const HARD: Self = Self {
+        bullet_period_ms: 100,
+        march_period_ms: 700,
+        min_march_period_ms: 200,
+        enemy_bullet_period_ms: 200,
+        enemy_fire_period_ms: 500,
+        enemy_fire_chance: 2,
+        mystery_ship_period_ms: 10_000,
+        mystery_ship_step_ms: 100,
+        max_bullets: 1,
+        fire_cooldown_ms: 400,
+        power_up_chance: 8,
+        power_up_fall_ms: 250,
+        power_up_duration_ms: 6_000,
+        dive_period_ms: 2_000,
+        dive_step_ms: 200,
+        waves: &[levels::VANGUARD, levels::SWARM],
+        boss: Some(BossWave {
+            after_waves: 1,
+            hp: 12,
+            step_ms: 350,
+            fire_period_ms: 1_500,
+            shields: NO_SHIELDS,
+        }),
+        lives: 1,
+        transition: Some(TransitionEffect::Wipe),
+        transition_ms: 200,
+        ..Self::NORMAL
+    };
+
+    /// Check that the game can actually be played with these rules.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let nonzero = [
+            self.bullet_period_ms,
+            self.min_march_period_ms,
+            self.enemy_bullet_period_ms,
+            self.enemy_fire_period_ms,
+            self.enemy_fire_chance,
+            self.mystery_ship_period_ms,
+            self.mystery_ship_step_ms,
+            self.max_bullets as u32,
+            self.power_up_chance,
+            self.power_up_fall_ms,
+            self.power_up_duration_ms,
+            self.dive_period_ms,
+            self.dive_step_ms,
+            self.start_animation_step_ms,
+            self.win_animation_step_ms,
+            self.loss_animation_step_ms,
+            self.boss_victory_animation_step_ms,
+        ];
+        if nonzero.contains(&0) {
+            return Err(ConfigError::Zero);
+        }
+
+        if self.min_march_period_ms > self.march_period_ms {
+            return Err(ConfigError::MarchPeriod);
+        }
+
+        if self.waves.is_empty() {
+            return Err(ConfigError::NoWaves);
+        }
+        for (wave, w) in self.waves.iter().enumerate() {
+            w.validate(wave)?;
+        }
+
+        if let Some(boss) = &self.boss {
+            if [boss.hp as u32, boss.step_ms, boss.fire_period_ms].contains(&0) {
+                return Err(ConfigError::Zero);
+            }
+            if !(1..=self.waves.len()).contains(&boss.after_waves) {
+                return Err(ConfigError::BossWave);
+            }
+        }
+
+        if self.lives == 0 {
+            return Err(ConfigError::NoLives);
+        }
+
+        if self.transition.is_some() && self.transition_ms == 0 {
+            return Err(ConfigError::Zero);
+        }
+
+        Ok(())
+    }
+}
+
+impl Wave {
+    /// Scale one of the config's march periods by this wave's speed.
+    pub fn march_period_ms(&self, period_ms: u32) -> u32 {
+        (period_ms.saturating_mul(100) / self.march_speed_percent).max(1)
+    }
+
+    fn validate(&self, wave: usize) -> Result<(), ConfigError> {
+        if self.march_speed_percent == 0 {
+            return Err(ConfigError::Zero);
+        }
+        if self.formations.is_empty() {
+            return Err(ConfigError::NoFormations { wave });
+        }
+
+        let last = DISPLAY_SIZE as usize - 1;
+        for (formation, f) in self.formations.iter().enumerate() {
+            if !f.iter().flatten().any(Option::is_some) {
+                return Err(ConfigError::EmptyFormation { wave, formation });
+            }
+            if f[last].iter().any(Option::is_some) {
+                return Err(ConfigError::FormationTooLow { wave, formation });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use crate::display::DISPLAY_SIZE;
+
+/// Armoured enemies blink until they're damaged, so they stand out from the
+/// rest. This is the period of the blink, in milliseconds.
+const ARMOUR_BLINK_MS: u32 = 400;
+
+/// Which enemies start where, in a wave.
+pub type Formation = [[Option<EnemyKind>; DISPLAY_SIZE as usize]; DISPLAY_SIZE as usize];
+
+/// The enemies on screen, during a wave.
+pub type EnemyGrid = [[Option<Enemy>; DISPLAY_SIZE as usize]; DISPLAY_SIZE as usize];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnemyKind {
+    /// Destroyed by a single hit.
+    Grunt,
+    /// Takes two hits.
+    Armoured,
+    /// Splits into two grunts when it's destroyed.
+    Splitter,
+}
+
+impl EnemyKind {
+    /// How many hits it takes to destroy.
+    pub fn hp(self) -> u8 {
+        match self {
+            EnemyKind::Grunt | EnemyKind::Splitter => 1,
+            EnemyKind::Armoured => 2,
+        }
+    }
+
+    /// How many points the player scores for destroying it.
+    pub fn points(self) -> u32 {
+        match self {
+            EnemyKind::Grunt => 10,
+            EnemyKind::Armoured | EnemyKind::Splitter => 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Enemy {
+    pub kind: EnemyKind,
+    /// The hits remaining until it's destroyed.
+    pub hp: u8,
+}
+
+impl Enemy {
+    pub fn new(kind: EnemyKind) -> Self {
+        Self {
+            kind,
+            hp: kind.hp(),
+        }
+    }
+
+    /// Enemies with more than one hit point left blink off briefly, every so
+    /// often. Once an armoured enemy is damaged, it looks like any other.
+    pub fn visible(&self, elapsed_ms: u32) -> bool {
+        self.hp <= 1 || elapsed_ms % ARMOUR_BLINK_MS >= ARMOUR_BLINK_MS / 4
+    }
+}
@@ -0,0 +1,54 @@
+/// Something that happens periodically, e.g. the enemies marching.
+///
+/// Call `tick` with how much time has passed; it returns true each time another
+/// `period_ms` has gone by.
+#[derive(Debug, Clone)]
+pub struct Interval {
+    period_ms: u32,
+    remaining_ms: u32,
+}
+
+impl Interval {
+    /// The first period starts now. `period_ms` must be non-zero.
+    pub fn new(period_ms: u32) -> Self {
+        debug_assert_ne!(period_ms, 0);
+        Self {
+            period_ms,
+            remaining_ms: period_ms,
+        }
+    }
+
+    /// Advance by `elapsed_ms` milliseconds. Returns true if a period just
+    /// ended.
+    ///
+    /// If several periods end at once, this still only returns true once, so
+    /// e.g. the enemies never jump more than one step at a time. The leftover
+    /// time carries over into the next period.
+    pub fn tick(&mut self, elapsed_ms: u32) -> bool {
+        if elapsed_ms < self.remaining_ms {
+            self.remaining_ms -= elapsed_ms;
+            false
+        } else {
+            let overshoot_ms = (elapsed_ms - self.remaining_ms) % self.period_ms;
+            self.remaining_ms = self.period_ms - overshoot_ms;
+            true
+        }
+    }
+
+    /// Start a new period, of a different length, right now.
+    pub fn restart(&mut self, period_ms: u32) {
+        debug_assert_ne!(period_ms, 0);
+        self.period_ms = period_ms;
+        self.remaining_ms = period_ms;
+    }
+
+    /// Change how often this fires.
+    ///
+    /// If the current period would now end sooner, it's shortened, so that
+    /// speeding up takes effect immediately.
+    pub fn set_period(&mut self, period_ms: u32) {
+        debug_assert_ne!(period_ms, 0);
+        self.period_ms = period_ms;
+        self.remaining_ms = self.remaining_ms.min(period_ms);
+    }
+}
@@ -0,0 +1,8 @@
+//! The waves defined in `levels/waves.txt`, compiled into flash by `build.rs`.
+
+use super::{
+    config::{Movement, Wave},
+    enemy::EnemyKind,
+};
+
+include!(concat!(env!("OUT_DIR"), "/levels.rs"));
@@ -1,52 +1,79 @@
-use super::{playing::Playing, start_animation::StartAnimation, GamePhase, Phase};
+use super::{
+    animation::{Animation, Effect, Keyframe, Picture},
+    playing::Playing,
+    rng::Rng,
+    start_animation::StartAnimation,
+    GamePhase, Phase,
+};
 use crate::display::{BoolGrid, DISPLAY_SIZE};
 
+const KEYFRAMES: &[Keyframe] = &[
+    // Blink whatever lost the game: the enemies in the bottom row, or the
+    // player, if they were shot.
+    Keyframe {
+        steps: 4,
+        effect: Effect::Blink {
+            picture: Picture::Scene,
+            blink: Picture::Highlight,
+        },
+    },
+    Keyframe {
+        steps: 2,
+        effect: Effect::Show(Picture::Scene),
+    },
+    // Blank screen briefly before restarting.
+    Keyframe {
+        steps: 1,
+        effect: Effect::Show(Picture::Blank),
+    },
+];
+
 pub struct LossAnimation {
     game_state: Playing,
-    num_updates: u32,
+    animation: Animation,
 }
 
 impl LossAnimation {
     pub fn new(game_state: Playing) -> Self {
+        let row = DISPLAY_SIZE as usize - 1;
+        let mut highlight = BoolGrid::default();
+        for (pixel, enemy) in highlight[row].iter_mut().zip(game_state.enemies[row]) {
+            *pixel = enemy.is_some();
+        }
+        if game_state.player_hit() {
+            highlight[row][game_state.player_x as usize] = true;
+        }
+
+        let mut scene = BoolGrid::default();
+        game_state.display(&mut scene);
+        for (scene_row, highlight_row) in scene.iter_mut().zip(highlight) {
+            for (pixel, highlighted) in scene_row.iter_mut().zip(highlight_row) {
+                *pixel |= highlighted;
+            }
+        }
+
+        let animation = Animation::new(KEYFRAMES, game_state.config.loss_animation_step_ms)
+            .with_scene(scene)
+            .with_highlight(highlight);
         Self {
             game_state,
-            num_updates: 0,
+            animation,
         }
     }
 }
 
 impl GamePhase for LossAnimation {
     fn display(&self, display_buffer: &mut BoolGrid) {
-        *display_buffer = [[false; 5]; 5];
-
-        // Blank screen briefly before restarting.
-        if self.num_updates >= 6 {
-            return;
-        }
-
-        self.game_state.display(display_buffer);
-
-        // Blink enemies in the bottom row.
-        let show = self.num_updates % 2 == 0 || self.num_updates >= 4;
-        for col in 0..DISPLAY_SIZE as usize {
-            let row = DISPLAY_SIZE as usize - 1;
-            if self.game_state.enemies[row][col] {
-                display_buffer[row][col] = show;
-            }
-        }
+        self.animation.display(display_buffer);
     }
 
-    fn update_timer_ms(&self) -> u32 {
-        500
-    }
-
-    fn update(&mut self) -> Option<Phase> {
-        self.num_updates += 1;
-
-        if self.num_updates < 7 {
-            None
+    fn update(&mut self, elapsed_ms: u32, _rng: &mut Rng) -> Option<Phase> {
+        if self.animation.update(elapsed_ms) {
+            Some(Phase::StartAnimation(StartAnimation::new(
+                self.game_state.config,
+            )))
         } else {
-            Some(Phase::StartAnimation(StartAnimation::new()))
+            None
         }
     }
 }
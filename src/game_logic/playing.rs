@@ -1,53 +1,237 @@
-use super::{loss_animation::LossAnimation, win_animation::WinAnimation, GamePhase, Phase};
+use self::{
+    boss::Boss,
+    diver::Diver,
+    march::March,
+    mystery_ship::MysteryShip,
+    power_up::{ActivePowerUp, PowerUp, PowerUpGrid},
+};
+use super::{
+    boss_victory_animation::BossVictoryAnimation,
+    config::BossWave,
+    enemy::{Enemy, EnemyGrid, EnemyKind},
+    interval::Interval,
+    loss_animation::LossAnimation,
+    rng::Rng,
+    win_animation::WinAnimation,
+    GameConfig, GamePhase, Phase,
+};
 use crate::{
     buttons::ButtonAction,
     display::{BoolGrid, DISPLAY_SIZE},
+    log::log,
 };
 
+mod boss;
+mod diver;
+mod march;
+mod mystery_ship;
+mod power_up;
+
+#[cfg(test)]
+mod tests;
+
+/// Points for shooting down the mystery ship.
+const MYSTERY_SHIP_POINTS: u32 = 50;
+
+/// Points for each hit on the boss.
+const BOSS_HIT_POINTS: u32 = 5;
+
+/// Extra points for defeating the boss.
+const BOSS_POINTS: u32 = 200;
+
+/// How many enemies can be diving at once.
+const MAX_DIVERS: usize = 2;
+
+/// When the player tries to fire but can't, the cannon disappears for this
+/// long, in milliseconds.
+const REJECTED_SHOT_BLINK_MS: u32 = 100;
+
+/// Shields sit in the row just above the player. That's also where the
+/// player's bullets start out, so shields block the player's shots too.
+const SHIELD_ROW: usize = DISPLAY_SIZE as usize - 2;
+
+/// How many hits a shield can take.
+const SHIELD_HP: u8 = 3;
+
+/// Damaged shields blink, so they look dimmer. This is the period of the blink,
+/// in milliseconds.
+const SHIELD_BLINK_MS: u32 = 200;
+
 #[derive(Debug, Clone)]
 pub struct Playing {
+    pub config: &'static GameConfig,
+    /// Index into `config.waves`. During the boss wave, this is the wave just
+    /// before it.
+    pub wave: usize,
     pub player_x: i8,
     pub bullets: BoolGrid,
-    pub enemies: BoolGrid,
-    num_updates: u32,
+    /// Enemy bullets move downwards, towards the player.
+    pub enemy_bullets: BoolGrid,
+    pub enemies: EnemyGrid,
+    /// Enemies that have left the formation.
+    pub divers: [Option<Diver>; MAX_DIVERS],
+    /// The hit points remaining for the shield in each column, if any.
+    pub shields: [u8; DISPLAY_SIZE as usize],
+    /// Flies across the top row, every so often.
+    pub mystery_ship: Option<MysteryShip>,
+    /// Only during the boss wave.
+    pub boss: Option<Boss>,
+    /// Dropped by destroyed enemies, falling towards the player.
+    pub power_ups: PowerUpGrid,
+    /// The power-up the player caught most recently, until it wears off.
+    pub power_up: Option<ActivePowerUp>,
+    pub lives: u8,
+    pub score: u32,
+    /// How long this wave has been going on, in milliseconds.
+    elapsed_ms: u32,
+    /// How many enemies there were at the start. The fewer remain, the faster
+    /// they march.
+    initial_enemies: u32,
+    /// Which way the enemies move next.
+    march: March,
+    /// How long until the player can fire again, in milliseconds.
+    fire_cooldown_ms: u32,
+    /// If non-zero, the player recently tried to fire but couldn't. Blink the
+    /// cannon for this many more milliseconds.
+    rejected_shot_ms: u32,
+
+    // Everything moves on its own schedule.
+    march_timer: Interval,
+    bullet_timer: Interval,
+    enemy_bullet_timer: Interval,
+    enemy_fire_timer: Interval,
+    power_up_timer: Interval,
+    dive_timer: Interval,
+    /// Counts down until the mystery ship next appears.
+    mystery_ship_timer: Interval,
 }
 
 impl Playing {
-    pub fn new() -> Self {
+    pub fn new(config: &'static GameConfig, rng: &mut Rng) -> Self {
+        Self::start_wave(config, 0, None, config.lives, 0, rng)
+    }
+
+    /// Move on to the next wave, if there is one.
+    pub fn next_wave(&self, rng: &mut Rng) -> Option<Self> {
+        if let Some(boss) = &self.config.boss {
+            if self.boss.is_none() && boss.after_waves == self.wave + 1 {
+                log!(game, info, "boss wave");
+                return Some(Self::start_wave(
+                    self.config,
+                    self.wave,
+                    Some(boss),
+                    self.lives,
+                    self.score,
+                    rng,
+                ));
+            }
+        }
+
+        let wave = self.wave + 1;
+        if wave < self.config.waves.len() {
+            Some(Self::start_wave(
+                self.config,
+                wave,
+                None,
+                self.lives,
+                self.score,
+                rng,
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Start `config.waves[wave]`, or the boss wave if `boss` is given.
+    fn start_wave(
+        config: &'static GameConfig,
+        wave: usize,
+        boss: Option<&BossWave>,
+        lives: u8,
+        score: u32,
+        rng: &mut Rng,
+    ) -> Self {
+        let w = &config.waves[wave];
+        let (enemies, shields) = match boss {
+            Some(boss) => (EnemyGrid::default(), boss.shields),
+            None => {
+                let formation = w.formations[rng.below(w.formations.len() as u32) as usize];
+                let enemies = formation.map(|row| row.map(|kind| kind.map(Enemy::new)));
+                (enemies, w.shields)
+            }
+        };
         let mut this = Self {
+            config,
+            wave,
             player_x: DISPLAY_SIZE / 2,
             bullets: [[false; 5]; 5],
-            enemies: [
-                [true, true, true, true, false], // 4 enemies
-                [false, false, false, false, false],
-                [false, false, false, false, false],
-                [false, false, false, false, false],
-                [false, false, false, false, false],
-            ],
-            num_updates: 0,
+            enemy_bullets: [[false; 5]; 5],
+            enemies,
+            divers: [const { None }; MAX_DIVERS],
+            shields: shields.map(|shield| if shield { SHIELD_HP } else { 0 }),
+            mystery_ship: None,
+            boss: boss.map(Boss::new),
+            power_ups: PowerUpGrid::default(),
+            power_up: None,
+            lives,
+            score,
+            elapsed_ms: 0,
+            initial_enemies: count(&enemies),
+            march: March::new(w.movement),
+            fire_cooldown_ms: 0,
+            rejected_shot_ms: 0,
+            march_timer: Interval::new(w.march_period_ms(config.march_period_ms)),
+            bullet_timer: Interval::new(config.bullet_period_ms),
+            enemy_bullet_timer: Interval::new(config.enemy_bullet_period_ms),
+            enemy_fire_timer: Interval::new(config.enemy_fire_period_ms),
+            power_up_timer: Interval::new(config.power_up_fall_ms),
+            dive_timer: Interval::new(config.dive_period_ms),
+            mystery_ship_timer: Interval::new(mystery_ship_delay_ms(config, rng)),
         };
 
         // Move the enemies immediately when the game starts. This gives the
         // player a visual cue that they can now act.
-        let outcome = this.update();
-        debug_assert!(outcome.is_none());
+        let enemies = this.march.next_step(&this.enemies);
+        this.move_all(
+            Moves {
+                enemies,
+                ..Moves::default()
+            },
+            rng,
+        );
 
         this
     }
 
     /// Perform an action in response to player input.
-    pub fn player_action(&mut self, action: ButtonAction) {
+    pub fn player_action(&mut self, action: ButtonAction, rng: &mut Rng) {
         match action {
             ButtonAction::Fire => {
-                let row = DISPLAY_SIZE as usize - 2;
-                let col = self.player_x as usize;
+                let rapid = self.power_up_active(PowerUp::Rapid);
+                let max_bullets = self.config.max_bullets as u32 * if rapid { 2 } else { 1 };
+                if self.fire_cooldown_ms != 0 || count(&self.bullets) >= max_bullets {
+                    self.rejected_shot_ms = REJECTED_SHOT_BLINK_MS;
+                    return;
+                }
+                if !rapid {
+                    self.fire_cooldown_ms = self.config.fire_cooldown_ms;
+                }
 
-                if self.enemies[row][col] {
-                    // Edge-case: the bullet immediately hits an enemy.
-                    self.enemies[row][col] = false;
+                // A spread shot fires as many of its bullets as there's room
+                // for, starting from the middle.
+                let x = self.player_x;
+                let cols: &[i8] = if self.power_up_active(PowerUp::Spread) {
+                    &[x, x - 1, x + 1]
                 } else {
-                    // Fire a bullet.
-                    self.bullets[row][col] = true;
+                    &[x]
+                };
+                let room = max_bullets - count(&self.bullets);
+                for &col in cols
+                    .iter()
+                    .filter(|&&col| in_bounds(0, col))
+                    .take(room as usize)
+                {
+                    self.fire(col as usize, rng);
                 }
             }
             ButtonAction::Left => {
@@ -59,6 +243,44 @@ impl Playing {
         }
         self.player_x = self.player_x.clamp(0, DISPLAY_SIZE - 1);
     }
+
+    /// Was the player hit by an enemy bullet, or a diving enemy?
+    pub fn player_hit(&self) -> bool {
+        let row = DISPLAY_SIZE - 1;
+        let col = self.player_x;
+        let diver_hit = self
+            .divers
+            .iter()
+            .flatten()
+            .any(|diver| (diver.row, diver.col) == (row, col));
+        self.enemy_bullets[row as usize][col as usize] || diver_hit
+    }
+
+    /// Fire a bullet from the row just above the player, in this column.
+    fn fire(&mut self, col: usize, rng: &mut Rng) {
+        let row = DISPLAY_SIZE as usize - 2;
+
+        if self.shields[col] != 0 {
+            // The bullet hits the player's own shield.
+            self.shields[col] -= 1;
+        } else if self.enemies[row][col].is_some() {
+            // Edge-case: the bullet immediately hits an enemy.
+            self.hit_enemy(row, col, rng);
+            if self.power_up_active(PowerUp::Piercing) {
+                self.bullets[row][col] = true;
+            }
+        } else if self.enemy_bullets[row][col] {
+            // Or an enemy's bullet.
+            self.enemy_bullets[row][col] = false;
+        } else {
+            // Fire a bullet.
+            self.bullets[row][col] = true;
+        }
+    }
+
+    fn power_up_active(&self, kind: PowerUp) -> bool {
+        self.power_up.as_ref().is_some_and(|p| p.kind == kind)
+    }
 }
 
 impl GamePhase for Playing {
@@ -67,109 +289,629 @@ impl GamePhase for Playing {
 
         for row in 0..DISPLAY_SIZE as usize {
             for col in 0..DISPLAY_SIZE as usize {
-                if self.bullets[row][col] || self.enemies[row][col] {
+                if self.bullets[row][col] || self.enemy_bullets[row][col] {
                     display_buffer[row][col] = true;
                 }
+                if let Some(enemy) = &self.enemies[row][col] {
+                    display_buffer[row][col] = enemy.visible(self.elapsed_ms);
+                }
             }
         }
 
-        display_buffer[DISPLAY_SIZE as usize - 1][self.player_x as usize] = true;
-    }
+        for diver in self.divers.iter().flatten() {
+            display_buffer[diver.row as usize][diver.col as usize] =
+                diver.enemy.visible(self.elapsed_ms);
+        }
+
+        for (row, power_ups) in self.power_ups.iter().enumerate() {
+            for (col, power_up) in power_ups.iter().enumerate() {
+                if power_up.is_some() {
+                    display_buffer[row][col] = PowerUp::falling_visible(self.elapsed_ms);
+                }
+            }
+        }
+
+        if let Some(ship) = &self.mystery_ship {
+            display_buffer[0][ship.col as usize] = MysteryShip::visible(self.elapsed_ms);
+        }
 
-    fn update_timer_ms(&self) -> u32 {
-        500
+        if let Some(boss) = &self.boss {
+            for (row, boss_row) in boss.grid().iter().enumerate() {
+                for (col, &cell) in boss_row.iter().enumerate() {
+                    if cell {
+                        display_buffer[row][col] = true;
+                    }
+                }
+            }
+            // The boss's health bar shrinks from the right.
+            for pixel in &mut display_buffer[0][..boss.health_bar()] {
+                *pixel = true;
+            }
+        }
+
+        for (col, pixel) in display_buffer[SHIELD_ROW].iter_mut().enumerate() {
+            if self.shield_visible(col) {
+                *pixel = true;
+            }
+        }
+
+        let power_up_blink = match &self.power_up {
+            Some(power_up) => power_up.cannon_visible(self.elapsed_ms),
+            None => true,
+        };
+        display_buffer[DISPLAY_SIZE as usize - 1][self.player_x as usize] =
+            self.rejected_shot_ms == 0 && power_up_blink;
     }
 
-    fn update(&mut self) -> Option<Phase> {
-        self.move_enemies();
-        self.move_bullets();
-        self.num_updates += 1;
+    fn update(&mut self, elapsed_ms: u32, rng: &mut Rng) -> Option<Phase> {
+        self.elapsed_ms += elapsed_ms;
+        self.fire_cooldown_ms = self.fire_cooldown_ms.saturating_sub(elapsed_ms);
+        self.rejected_shot_ms = self.rejected_shot_ms.saturating_sub(elapsed_ms);
+        if self.power_up.as_mut().is_some_and(|p| p.tick(elapsed_ms)) {
+            self.power_up = None;
+        }
+
+        if self.mystery_ship_timer.tick(elapsed_ms) {
+            self.launch_mystery_ship(rng);
+        }
+        if self.dive_timer.tick(elapsed_ms) {
+            self.launch_diver(rng);
+        }
+
+        let mut moves = Moves::default();
+        if self.march_timer.tick(elapsed_ms) {
+            moves.enemies = self.march.next_step(&self.enemies);
+        }
+        if self.bullet_timer.tick(elapsed_ms) {
+            moves.bullets = UP;
+        }
+        if self.enemy_bullet_timer.tick(elapsed_ms) {
+            moves.enemy_bullets = DOWN;
+        }
+        if self.power_up_timer.tick(elapsed_ms) {
+            moves.power_ups = DOWN;
+        }
+        if let Some(ship) = &mut self.mystery_ship {
+            moves.mystery_ship = ship.tick(elapsed_ms);
+        }
+        if let Some(boss) = &mut self.boss {
+            moves.boss = boss.tick(elapsed_ms);
+        }
+        for (diver, step) in self.divers.iter_mut().zip(&mut moves.divers) {
+            if let Some(diver) = diver {
+                *step = diver.tick(elapsed_ms, self.player_x);
+            }
+        }
+        self.move_all(moves, rng);
+        self.catch_power_up();
+
+        if self.enemy_fire_timer.tick(elapsed_ms) {
+            self.enemy_fire(rng);
+        }
+        if self.boss.as_mut().is_some_and(|b| b.tick_fire(elapsed_ms)) {
+            self.boss_fire();
+        }
         self.check_gameover()
     }
 }
 
+/// A movement of at most one cell, as `(drow, dcol)`.
+type Step = (i8, i8);
+
+const STAY: Step = (0, 0);
+const UP: Step = (-1, 0);
+const DOWN: Step = (1, 0);
+
+/// How far each group of things moves during one update.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Moves {
+    enemies: Step,
+    bullets: Step,
+    enemy_bullets: Step,
+    mystery_ship: Step,
+    boss: Step,
+    power_ups: Step,
+    divers: [Step; MAX_DIVERS],
+}
+
 impl Playing {
-    fn move_enemies(&mut self) {
-        // Enemies move every other tick.
-        if self.num_updates % 2 != 0 {
+    /// Move the enemies, the player's bullets, the enemies' bullets, the
+    /// mystery ship, the boss, and so on, all at once. Each group moves as a
+    /// whole, apart from divers, which each move their own way.
+    ///
+    /// Collisions take into account where everything started, as well as where
+    /// it ended up. Otherwise, a bullet and an enemy moving towards each other
+    /// could swap cells, and pass right through each other.
+    fn move_all(&mut self, moves: Moves, rng: &mut Rng) {
+        if moves == Moves::default() {
             return;
         }
 
-        let cycle = self.num_updates / 2;
-        let (drow, dcol) = match cycle % 4 {
-            0 => (0, 1),     // right
-            1 | 3 => (1, 0), // down
-            2 => (0, -1),    // left
-            _ => unreachable!(),
-        };
-
-        let mut out = BoolGrid::default();
+        // The player's bullets are the only things that collide with all of
+        // the other groups, so check each of them in turn.
         for row in 0..DISPLAY_SIZE {
             for col in 0..DISPLAY_SIZE {
-                if self.enemies[row as usize][col as usize] {
-                    let r = row + drow;
-                    let c = col + dcol;
-                    assert!(in_bounds(r, c), "enemy moved off screen ({r}, {c})");
-                    out[r as usize][c as usize] = true;
+                if !self.bullets[row as usize][col as usize] {
+                    continue;
+                }
+                let from = (row, col);
+                let to = (row + moves.bullets.0, col + moves.bullets.1);
+
+                if let Some((r, c)) = swept_hit(&self.enemies, moves.enemies, from, to) {
+                    self.hit_enemy(r as usize, c as usize, rng);
+                    if self.power_up_active(PowerUp::Piercing) {
+                        continue;
+                    }
+                } else if let Some((r, c)) =
+                    swept_hit(&self.enemy_bullets, moves.enemy_bullets, from, to)
+                {
+                    self.enemy_bullets[r as usize][c as usize] = false;
+                } else if swept_hit(&self.mystery_ship_grid(), moves.mystery_ship, from, to)
+                    .is_some()
+                {
+                    self.mystery_ship = None;
+                    self.score += MYSTERY_SHIP_POINTS;
+                    log!(game, info, "mystery ship destroyed");
+                } else if self.hit_boss(moves.boss, from, to)
+                    || self.hit_diver(&moves.divers, from, to)
+                {
+                    // Already handled.
+                } else {
+                    continue;
                 }
+                self.bullets[row as usize][col as usize] = false;
             }
         }
-        self.enemies = out;
 
-        self.check_collision();
-    }
+        // The march never takes the formation off the screen.
+        shift(&mut self.enemies, moves.enemies);
 
-    fn move_bullets(&mut self) {
-        // Bullets in the top row simply disappear.
-        for col in 0..DISPLAY_SIZE as usize {
-            self.bullets[0][col] = false;
+        // Bullets simply disappear off the edge of the screen.
+        shift(&mut self.bullets, moves.bullets);
+        shift(&mut self.enemy_bullets, moves.enemy_bullets);
+        // As do power-ups the player doesn't catch.
+        shift(&mut self.power_ups, moves.power_ups);
+
+        // So does the mystery ship, once it's crossed the screen.
+        if let Some(ship) = &mut self.mystery_ship {
+            ship.col += moves.mystery_ship.1;
+            if !in_bounds(0, ship.col) {
+                self.mystery_ship = None;
+            }
         }
 
-        for row in 1..DISPLAY_SIZE as usize {
-            for col in 0..DISPLAY_SIZE as usize {
-                if self.bullets[row][col] {
-                    self.bullets[row][col] = false;
-                    self.bullets[row - 1][col] = true;
+        if let Some(boss) = &mut self.boss {
+            boss.col += moves.boss.1;
+        }
+
+        // Divers that miss the player carry on off the bottom of the screen,
+        // and escape.
+        for (slot, step) in self.divers.iter_mut().zip(moves.divers) {
+            if let Some(diver) = slot {
+                diver.row += step.0;
+                diver.col += step.1;
+                if !in_bounds(diver.row, diver.col) {
+                    *slot = None;
                 }
             }
         }
 
-        self.check_collision();
+        // Shields don't move, so something can only pass through a shield by
+        // landing on it.
+        self.check_shields();
+
+        if moves.enemies != STAY {
+            self.speed_up_march();
+        }
     }
 
-    /// If a bullet overlaps an enemy, both are destroyed.
-    fn check_collision(&mut self) {
-        for row in 0..DISPLAY_SIZE as usize {
-            for col in 0..DISPLAY_SIZE as usize {
-                if self.bullets[row][col] && self.enemies[row][col] {
-                    self.bullets[row][col] = false;
-                    self.enemies[row][col] = false;
+    /// Speed up as enemies are destroyed, from `march_period_ms` at the start
+    /// down to `min_march_period_ms` for the last one.
+    fn speed_up_march(&mut self) {
+        let wave = &self.config.waves[self.wave];
+        let slowest = wave.march_period_ms(self.config.march_period_ms);
+        let fastest = wave.march_period_ms(self.config.min_march_period_ms);
+        let initial = self.initial_enemies.saturating_sub(1).max(1);
+        // Splitters can leave more enemies than there were at the start.
+        let remaining = count(&self.enemies).saturating_sub(1).min(initial);
+        let period = fastest + (slowest - fastest) * remaining / initial;
+        self.march_timer.set_period(period.max(1));
+    }
+
+    /// Maybe pick a random column, and have its lowest enemy fire.
+    fn enemy_fire(&mut self, rng: &mut Rng) {
+        if !rng.chance(1, self.config.enemy_fire_chance) {
+            return;
+        }
+
+        let mut shooters = [None; DISPLAY_SIZE as usize];
+        let mut num_shooters = 0;
+        for col in 0..DISPLAY_SIZE as usize {
+            let lowest = (0..DISPLAY_SIZE as usize)
+                .rev()
+                .find(|&row| self.enemies[row][col].is_some());
+            if let Some(row) = lowest {
+                if row + 1 < DISPLAY_SIZE as usize {
+                    shooters[num_shooters] = Some((row + 1, col));
+                    num_shooters += 1;
                 }
             }
         }
+        if num_shooters == 0 {
+            return;
+        }
+
+        if let Some((row, col)) = shooters[rng.below(num_shooters as u32) as usize] {
+            if self.bullets[row][col] {
+                // Edge-case: the bullet immediately hits the player's bullet.
+                self.bullets[row][col] = false;
+            } else {
+                self.enemy_bullets[row][col] = true;
+                self.check_shields();
+            }
+        }
     }
 
-    fn check_gameover(&self) -> Option<Phase> {
-        // Did the enemies reach the bottom?
+    /// Knock a hit point off the enemy here, and destroy it if that was its
+    /// last. Destroyed enemies sometimes drop a power-up.
+    fn hit_enemy(&mut self, row: usize, col: usize, rng: &mut Rng) {
+        let Some(enemy) = &mut self.enemies[row][col] else {
+            return;
+        };
+        enemy.hp -= 1;
+        if enemy.hp != 0 {
+            return;
+        }
+
+        let kind = enemy.kind;
+        self.enemies[row][col] = None;
+        self.score += kind.points();
+        if kind == EnemyKind::Splitter {
+            self.split(row, col);
+        }
+        if rng.chance(1, self.config.power_up_chance) {
+            self.power_ups[row][col] = Some(PowerUp::random(rng));
+        }
+    }
+
+    /// The player catches a power-up by being underneath it, when it reaches
+    /// the bottom row. It replaces any power-up they already had.
+    fn catch_power_up(&mut self) {
+        let row = DISPLAY_SIZE as usize - 1;
+        let col = self.player_x as usize;
+        if let Some(kind) = self.power_ups[row][col].take() {
+            log!(game, info, "power-up: {}", kind);
+            self.power_up = Some(ActivePowerUp::new(kind, self.config.power_up_duration_ms));
+        }
+    }
+
+    /// A destroyed splitter leaves two grunts behind, either side of where it
+    /// was, if there's room.
+    fn split(&mut self, row: usize, col: usize) {
+        let mut remaining = 2;
+        for c in [col as i8 - 1, col as i8 + 1, col as i8] {
+            if remaining == 0 {
+                break;
+            }
+            if !in_bounds(row as i8, c) {
+                continue;
+            }
+
+            let c = c as usize;
+            let empty = self.enemies[row][c].is_none()
+                && !self.bullets[row][c]
+                && !self.enemy_bullets[row][c];
+            if empty {
+                self.enemies[row][c] = Some(Enemy::new(EnemyKind::Grunt));
+                remaining -= 1;
+            }
+        }
+    }
+
+    /// In waves with divers, pick a random column, and send its lowest enemy
+    /// diving at the player.
+    fn launch_diver(&mut self, rng: &mut Rng) {
+        if !self.config.waves[self.wave].divers || self.boss.is_some() {
+            return;
+        }
+        let Some(slot) = self.divers.iter().position(Option::is_none) else {
+            return;
+        };
+
+        let mut candidates = [None; DISPLAY_SIZE as usize];
+        let mut num_candidates = 0;
         for col in 0..DISPLAY_SIZE as usize {
-            if self.enemies[DISPLAY_SIZE as usize - 1][col] {
-                return Some(Phase::LossAnimation(LossAnimation::new(self.clone())));
+            let lowest = (0..DISPLAY_SIZE as usize)
+                .rev()
+                .find(|&row| self.enemies[row][col].is_some());
+            if let Some(row) = lowest {
+                candidates[num_candidates] = Some((row, col));
+                num_candidates += 1;
+            }
+        }
+        if num_candidates == 0 {
+            return;
+        }
+
+        if let Some((row, col)) = candidates[rng.below(num_candidates as u32) as usize] {
+            if let Some(enemy) = self.enemies[row][col].take() {
+                let step_ms = self.config.dive_step_ms;
+                self.divers[slot] = Some(Diver::new(row as i8, col as i8, enemy, step_ms));
+            }
+        }
+    }
+
+    /// If a bullet moving from `from` to `to` hits a diver, which is moving by
+    /// its entry in `steps`, damage the diver and return true. Divers are
+    /// worth double points.
+    fn hit_diver(&mut self, steps: &[Step; MAX_DIVERS], from: (i8, i8), to: (i8, i8)) -> bool {
+        for (slot, &step) in self.divers.iter_mut().zip(steps) {
+            let Some(diver) = slot else {
+                continue;
+            };
+            let mut grid = BoolGrid::default();
+            grid[diver.row as usize][diver.col as usize] = true;
+            if swept_hit(&grid, step, from, to).is_none() {
+                continue;
+            }
+
+            diver.enemy.hp -= 1;
+            if diver.enemy.hp == 0 {
+                self.score += 2 * diver.enemy.kind.points();
+                *slot = None;
+            }
+            return true;
+        }
+        false
+    }
+
+    /// If a bullet moving from `from` to `to` hits the boss, which is moving by
+    /// `step`, damage the boss and return true.
+    fn hit_boss(&mut self, step: Step, from: (i8, i8), to: (i8, i8)) -> bool {
+        let Some(boss) = &mut self.boss else {
+            return false;
+        };
+        if boss.hp == 0 || swept_hit(&boss.grid(), step, from, to).is_none() {
+            return false;
+        }
+
+        boss.hp -= 1;
+        self.score += BOSS_HIT_POINTS;
+        if boss.hp == 0 {
+            self.score += BOSS_POINTS;
+        }
+        true
+    }
+
+    /// The boss fires a spread of bullets, one from each of its columns.
+    fn boss_fire(&mut self) {
+        let Some(boss) = &self.boss else {
+            return;
+        };
+        if boss.hp == 0 {
+            return;
+        }
+
+        for (row, col) in boss.guns() {
+            if self.bullets[row][col] {
+                // Edge-case: the bullet immediately hits the player's bullet.
+                self.bullets[row][col] = false;
+            } else {
+                self.enemy_bullets[row][col] = true;
             }
         }
+        self.check_shields();
+    }
+
+    /// The mystery ship only appears if the top row is clear, so that it
+    /// doesn't overlap the formation, or the boss's health bar.
+    fn launch_mystery_ship(&mut self, rng: &mut Rng) {
+        self.mystery_ship_timer
+            .restart(mystery_ship_delay_ms(self.config, rng));
+
+        let top_row_clear = self.enemies[0].iter().all(Option::is_none) && self.boss.is_none();
+        if self.mystery_ship.is_none() && top_row_clear {
+            self.mystery_ship = Some(MysteryShip::new(self.config.mystery_ship_step_ms, rng));
+        }
+    }
+
+    fn mystery_ship_grid(&self) -> BoolGrid {
+        let mut grid = BoolGrid::default();
+        if let Some(ship) = &self.mystery_ship {
+            grid[0][ship.col as usize] = true;
+        }
+        grid
+    }
+
+    /// Shields absorb bullets from both sides, and are crushed by enemies
+    /// marching into them.
+    fn check_shields(&mut self) {
+        for col in 0..DISPLAY_SIZE as usize {
+            if self.shields[col] == 0 {
+                continue;
+            }
+
+            if self.enemies[SHIELD_ROW][col].is_some() {
+                self.shields[col] = 0;
+                continue;
+            }
+
+            // A diver crashes into the shield, destroying both.
+            for slot in &mut self.divers {
+                if slot
+                    .as_ref()
+                    .is_some_and(|diver| (diver.row, diver.col) == (SHIELD_ROW as i8, col as i8))
+                {
+                    *slot = None;
+                    self.shields[col] = 0;
+                }
+            }
+            if self.shields[col] == 0 {
+                continue;
+            }
+
+            for bullets in [&mut self.bullets, &mut self.enemy_bullets] {
+                if bullets[SHIELD_ROW][col] {
+                    bullets[SHIELD_ROW][col] = false;
+                    self.shields[col] = self.shields[col].saturating_sub(1);
+                }
+            }
+        }
+    }
+
+    /// Damaged shields blink: the weaker they are, the less time they spend
+    /// lit.
+    fn shield_visible(&self, col: usize) -> bool {
+        let hp = self.shields[col] as u32;
+        let time_lit = SHIELD_BLINK_MS * hp / SHIELD_HP as u32;
+        self.elapsed_ms % SHIELD_BLINK_MS < time_lit
+    }
+
+    /// Remove any enemy bullet or diver in the player's cell.
+    fn clear_player_cell(&mut self) {
+        let (row, col) = (DISPLAY_SIZE - 1, self.player_x);
+        self.enemy_bullets[row as usize][col as usize] = false;
+        for slot in &mut self.divers {
+            if slot
+                .as_ref()
+                .is_some_and(|diver| (diver.row, diver.col) == (row, col))
+            {
+                *slot = None;
+            }
+        }
+    }
+
+    fn check_gameover(&mut self) -> Option<Phase> {
+        // If the player was hit, but has lives to spare, clear the screen of
+        // enemy bullets and keep going. A shield power-up stops whatever hit
+        // them altogether.
+        if self.player_hit() && self.power_up_active(PowerUp::Shield) {
+            self.clear_player_cell();
+        } else if self.player_hit() {
+            self.lives -= 1;
+            if self.lives != 0 {
+                self.enemy_bullets = BoolGrid::default();
+                self.clear_player_cell();
+            }
+        }
+
+        // Did the enemies reach the bottom, or shoot the player one too many
+        // times?
+        let reached_bottom = (0..DISPLAY_SIZE as usize)
+            .any(|col| self.enemies[DISPLAY_SIZE as usize - 1][col].is_some());
+        if reached_bottom || self.lives == 0 {
+            log!(game, info, "game over, score: {}", self.score);
+            return Some(Phase::LossAnimation(LossAnimation::new(self.clone())));
+        }
+
+        // The boss wave is won by defeating the boss.
+        if let Some(boss) = &self.boss {
+            if boss.hp != 0 {
+                return None;
+            }
+            log!(game, info, "boss defeated, score: {}", self.score);
+            return Some(Phase::BossVictoryAnimation(BossVictoryAnimation::new(
+                self.clone(),
+            )));
+        }
 
         // Are there any enemies remaining?
+        if self.divers.iter().any(Option::is_some) {
+            return None;
+        }
         for row in 0..DISPLAY_SIZE as usize {
             for col in 0..DISPLAY_SIZE as usize {
-                if self.enemies[row][col] {
+                if self.enemies[row][col].is_some() {
                     return None;
                 }
             }
         }
 
+        log!(
+            game,
+            info,
+            "wave {} cleared, score: {}",
+            self.wave,
+            self.score
+        );
         Some(Phase::WinAnimation(WinAnimation::new(self.clone())))
     }
 }
 
+/// How long until the mystery ship next appears: somewhere between half and
+/// one and a half times `mystery_ship_period_ms`.
+fn mystery_ship_delay_ms(config: &GameConfig, rng: &mut Rng) -> u32 {
+    let period = config.mystery_ship_period_ms;
+    (period / 2 + rng.below(period)).max(1)
+}
+
+/// A grid of anything: `bool` for bullets, `Option<Enemy>` for enemies. A cell
+/// is empty if it holds the default value.
+type Grid<T> = [[T; DISPLAY_SIZE as usize]; DISPLAY_SIZE as usize];
+
+/// Something is moving from `from` to `to`. If it collides with any of
+/// `others`, which are all moving by `step`, returns the position (before
+/// moving) of the one it hits.
+fn swept_hit<T: Copy + Default + PartialEq>(
+    others: &Grid<T>,
+    step: Step,
+    from: (i8, i8),
+    to: (i8, i8),
+) -> Option<(i8, i8)> {
+    let occupied = |(row, col): (i8, i8)| {
+        in_bounds(row, col) && others[row as usize][col as usize] != T::default()
+    };
+
+    // They end up in the same cell.
+    let meet = (to.0 - step.0, to.1 - step.1);
+    if occupied(meet) {
+        return Some(meet);
+    }
+
+    // They pass through each other half way: either by swapping cells, or by
+    // crossing like an X when moving diagonally. Relative to the other one, this
+    // one moves by an even number of cells, and meets it in the middle.
+    let (drow, dcol) = (to.0 - from.0 - step.0, to.1 - from.1 - step.1);
+    if drow % 2 == 0 && dcol % 2 == 0 && (drow, dcol) != (0, 0) {
+        let cross = (from.0 + drow / 2, from.1 + dcol / 2);
+        if occupied(cross) {
+            return Some(cross);
+        }
+    }
+
+    None
+}
+
+/// Move everything in the grid by `step`. Anything that moves off the edge of
+/// the screen disappears.
+fn shift<T: Copy + Default + PartialEq>(grid: &mut Grid<T>, step: Step) {
+    if step == STAY {
+        return;
+    }
+
+    let mut out = Grid::<T>::default();
+    for row in 0..DISPLAY_SIZE {
+        for col in 0..DISPLAY_SIZE {
+            let cell = grid[row as usize][col as usize];
+            if cell != T::default() {
+                let r = row + step.0;
+                let c = col + step.1;
+                if in_bounds(r, c) {
+                    out[r as usize][c as usize] = cell;
+                }
+            }
+        }
+    }
+    *grid = out;
+}
+
+fn count<T: Copy + Default + PartialEq>(grid: &Grid<T>) -> u32 {
+    grid.iter()
+        .flatten()
+        .filter(|&&cell| cell != T::default())
+        .count() as u32
+}
+
 fn in_bounds(row: i8, col: i8) -> bool {
     let row = 0 <= row && row < DISPLAY_SIZE;
     let col = 0 <= col && col < DISPLAY_SIZE;
@@ -0,0 +1,101 @@
+use super::{Step, STAY};
+use crate::{
+    display::{BoolGrid, DISPLAY_SIZE},
+    game_logic::{config::BossWave, interval::Interval},
+};
+
+const O: bool = false;
+const X: bool = true;
+
+/// What the boss looks like. Its top-left corner is at `(TOP_ROW, col)`.
+const SHAPE: [[bool; WIDTH as usize]; 2] = [
+    [X, X, X], //
+    [X, O, X],
+];
+
+const WIDTH: i8 = 3;
+
+/// The top row of the screen is taken up by the boss's health bar.
+const TOP_ROW: i8 = 1;
+
+/// A big enemy, several pixels across, that slides from side to side and fires
+/// a spread of bullets.
+#[derive(Debug, Clone)]
+pub struct Boss {
+    /// The leftmost column the boss occupies.
+    pub col: i8,
+    /// Either 1 (right) or -1 (left).
+    dir: i8,
+    pub hp: u8,
+    max_hp: u8,
+    step_timer: Interval,
+    fire_timer: Interval,
+}
+
+impl Boss {
+    /// The boss starts in the middle of the screen, heading right.
+    pub fn new(wave: &BossWave) -> Self {
+        Self {
+            col: (DISPLAY_SIZE - WIDTH) / 2,
+            dir: 1,
+            hp: wave.hp,
+            max_hp: wave.hp,
+            step_timer: Interval::new(wave.step_ms),
+            fire_timer: Interval::new(wave.fire_period_ms),
+        }
+    }
+
+    /// Advance by `elapsed_ms`. Returns how far the boss moves, turning around
+    /// at the edges of the screen.
+    pub fn tick(&mut self, elapsed_ms: u32) -> Step {
+        if !self.step_timer.tick(elapsed_ms) {
+            return STAY;
+        }
+        if !(0..=DISPLAY_SIZE - WIDTH).contains(&(self.col + self.dir)) {
+            self.dir = -self.dir;
+        }
+        (0, self.dir)
+    }
+
+    /// Advance by `elapsed_ms`. Returns true if it's time to fire.
+    pub fn tick_fire(&mut self, elapsed_ms: u32) -> bool {
+        self.fire_timer.tick(elapsed_ms)
+    }
+
+    /// The cells the boss occupies.
+    pub fn grid(&self) -> BoolGrid {
+        let mut grid = BoolGrid::default();
+        for (r, shape_row) in SHAPE.iter().enumerate() {
+            for (c, &cell) in shape_row.iter().enumerate() {
+                let row = TOP_ROW as usize + r;
+                let col = self.col as usize + c;
+                grid[row][col] = cell;
+            }
+        }
+        grid
+    }
+
+    /// Where the boss fires from: just below the bottom of each of its
+    /// columns.
+    pub fn guns(&self) -> [(usize, usize); WIDTH as usize] {
+        core::array::from_fn(|c| {
+            let depth = SHAPE.iter().rposition(|row| row[c]).unwrap_or(0);
+            let row = TOP_ROW as usize + depth + 1;
+            (row, self.col as usize + c)
+        })
+    }
+
+    /// The middle of the boss, where it explodes from.
+    pub fn centre(&self) -> (i8, i8) {
+        (TOP_ROW, self.col + WIDTH / 2)
+    }
+
+    /// How many pixels of the health bar to light, rounding up so that it
+    /// only empties when the boss is defeated.
+    pub fn health_bar(&self) -> usize {
+        let width = DISPLAY_SIZE as u32;
+        let hp = self.hp as u32;
+        let max_hp = self.max_hp as u32;
+        hp.saturating_mul(width).div_ceil(max_hp) as usize
+    }
+}
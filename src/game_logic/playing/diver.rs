@@ -0,0 +1,32 @@
+use super::{Step, STAY};
+use crate::game_logic::{enemy::Enemy, interval::Interval};
+
+/// An enemy that has left the formation, and swoops down towards the player.
+#[derive(Debug, Clone)]
+pub struct Diver {
+    pub row: i8,
+    pub col: i8,
+    pub enemy: Enemy,
+    step_timer: Interval,
+}
+
+impl Diver {
+    pub fn new(row: i8, col: i8, enemy: Enemy, step_ms: u32) -> Self {
+        Self {
+            row,
+            col,
+            enemy,
+            step_timer: Interval::new(step_ms),
+        }
+    }
+
+    /// Advance by `elapsed_ms`. Returns how far the diver moves: always down,
+    /// and sideways towards the player if it's not already above them.
+    pub fn tick(&mut self, elapsed_ms: u32, player_x: i8) -> Step {
+        if self.step_timer.tick(elapsed_ms) {
+            (1, (player_x - self.col).signum())
+        } else {
+            STAY
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use super::{Step, DOWN, STAY};
+use crate::{
+    display::DISPLAY_SIZE,
+    game_logic::{config::Movement, enemy::EnemyGrid},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Decides which way the enemy formation moves next.
+///
+/// It looks at where the enemies actually are, rather than assuming a shape, so
+/// the formation never moves off the screen.
+#[derive(Debug, Clone)]
+pub struct March {
+    movement: Movement,
+    /// Which way the formation moves sideways: either 1 (right) or -1 (left).
+    dir: i8,
+    /// How many times the formation has moved.
+    steps: u32,
+}
+
+impl March {
+    /// The formation starts off moving right.
+    pub fn new(movement: Movement) -> Self {
+        Self {
+            movement,
+            dir: 1,
+            steps: 0,
+        }
+    }
+
+    /// Which way the formation moves next. Call this once per step, since it
+    /// keeps track of where in the pattern we are.
+    pub fn next_step(&mut self, enemies: &EnemyGrid) -> Step {
+        let Some((left, right)) = columns(enemies) else {
+            return STAY;
+        };
+        let fits = |dir: i8| 0 <= left + dir && right + dir < DISPLAY_SIZE;
+
+        let step = match self.movement {
+            Movement::Shuffle => match self.steps % 4 {
+                0 if fits(1) => (0, 1),
+                2 if fits(-1) => (0, -1),
+                _ => DOWN,
+            },
+            Movement::March => {
+                if fits(self.dir) {
+                    (0, self.dir)
+                } else {
+                    self.dir = -self.dir;
+                    DOWN
+                }
+            }
+            Movement::Zigzag => {
+                if !self.steps.is_multiple_of(2) {
+                    DOWN
+                } else {
+                    if !fits(self.dir) {
+                        self.dir = -self.dir;
+                    }
+                    // A formation as wide as the screen can only go down.
+                    if fits(self.dir) {
+                        (0, self.dir)
+                    } else {
+                        DOWN
+                    }
+                }
+            }
+        };
+
+        self.steps += 1;
+        step
+    }
+}
+
+/// The leftmost and rightmost columns with an enemy in them, if any.
+fn columns(enemies: &EnemyGrid) -> Option<(i8, i8)> {
+    let occupied = |col: &i8| enemies.iter().any(|row| row[*col as usize].is_some());
+    let left = (0..DISPLAY_SIZE).find(occupied)?;
+    let right = (0..DISPLAY_SIZE).rev().find(occupied)?;
+    Some((left, right))
+}
@@ -0,0 +1,79 @@
+use super::March;
+use crate::{
+    display::DISPLAY_SIZE,
+    game_logic::{
+        config::Movement,
+        enemy::{Enemy, EnemyGrid, EnemyKind},
+        playing::{count, shift},
+        rng::Rng,
+        GameConfig,
+    },
+};
+
+/// Plenty of steps for any formation to reach the bottom.
+const MAX_STEPS: u32 = 200;
+
+/// Every wave in `levels/waves.txt` is in at least one of these; otherwise,
+/// the firmware wouldn't build without warnings.
+const CONFIGS: [GameConfig; 3] = [GameConfig::EASY, GameConfig::NORMAL, GameConfig::HARD];
+
+const MOVEMENTS: [Movement; 3] = [Movement::Shuffle, Movement::March, Movement::Zigzag];
+
+/// March `enemies` until they reach the bottom row, checking that none of
+/// them leave the screen on the way. Returns how many steps that took.
+///
+/// With `thin_out`, enemies are destroyed along the way, as in a real game, so
+/// the formation changes shape as it goes.
+fn march_to_bottom(mut enemies: EnemyGrid, movement: Movement, thin_out: bool) -> u32 {
+    let mut march = March::new(movement);
+    let mut rng = Rng::new(1);
+    let bottom = DISPLAY_SIZE as usize - 1;
+    for steps in 0..MAX_STEPS {
+        if enemies[bottom].iter().any(Option::is_some) {
+            return steps;
+        }
+
+        if thin_out && count(&enemies) > 1 && rng.chance(1, 3) {
+            let (row, col) = loop {
+                let row = rng.below(DISPLAY_SIZE as u32) as usize;
+                let col = rng.below(DISPLAY_SIZE as u32) as usize;
+                if enemies[row][col].is_some() {
+                    break (row, col);
+                }
+            };
+            enemies[row][col] = None;
+        }
+
+        let before = count(&enemies);
+        let step = march.next_step(&enemies);
+        shift(&mut enemies, step);
+        assert_eq!(count(&enemies), before, "an enemy left the screen");
+    }
+    panic!("never reached the bottom row");
+}
+
+#[test]
+fn formations_stay_on_screen_and_reach_the_bottom() {
+    for config in &CONFIGS {
+        for wave in config.waves {
+            for formation in wave.formations {
+                let enemies = formation.map(|row| row.map(|kind| kind.map(Enemy::new)));
+                for movement in MOVEMENTS {
+                    for thin_out in [false, true] {
+                        march_to_bottom(enemies, movement, thin_out);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn a_formation_as_wide_as_the_screen_only_comes_down() {
+    let mut enemies = EnemyGrid::default();
+    enemies[0] = [Some(Enemy::new(EnemyKind::Grunt)); DISPLAY_SIZE as usize];
+    for movement in MOVEMENTS {
+        let steps = march_to_bottom(enemies, movement, false);
+        assert_eq!(steps, DISPLAY_SIZE as u32 - 1, "{movement:?}");
+    }
+}
@@ -0,0 +1,48 @@
+use super::{Step, STAY};
+use crate::{
+    display::DISPLAY_SIZE,
+    game_logic::{interval::Interval, rng::Rng},
+};
+
+/// The mystery ship blinks quickly, so it can be told apart from ordinary
+/// enemies. This is the period of the blink, in milliseconds.
+const BLINK_MS: u32 = 80;
+
+/// A bonus target that flies across the top row, independently of the enemy
+/// formation.
+#[derive(Debug, Clone)]
+pub struct MysteryShip {
+    pub col: i8,
+    /// Either 1 (right) or -1 (left).
+    dir: i8,
+    step_timer: Interval,
+}
+
+impl MysteryShip {
+    /// The ship flies in from one side of the screen, picked at random.
+    pub fn new(step_ms: u32, rng: &mut Rng) -> Self {
+        let (col, dir) = if rng.chance(1, 2) {
+            (0, 1)
+        } else {
+            (DISPLAY_SIZE - 1, -1)
+        };
+        Self {
+            col,
+            dir,
+            step_timer: Interval::new(step_ms),
+        }
+    }
+
+    /// Advance by `elapsed_ms`. Returns how far the ship moves.
+    pub fn tick(&mut self, elapsed_ms: u32) -> Step {
+        if self.step_timer.tick(elapsed_ms) {
+            (0, self.dir)
+        } else {
+            STAY
+        }
+    }
+
+    pub fn visible(elapsed_ms: u32) -> bool {
+        elapsed_ms % BLINK_MS < BLINK_MS / 2
+    }
+}
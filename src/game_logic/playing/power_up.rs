@@ -0,0 +1,73 @@
+use crate::{display::DISPLAY_SIZE, game_logic::rng::Rng};
+
+/// Falling power-ups blink, so they can be told apart from enemy bullets. This
+/// is the period of the blink, in milliseconds.
+const FALLING_BLINK_MS: u32 = 160;
+
+/// The power-ups falling down the screen.
+pub type PowerUpGrid = [[Option<PowerUp>; DISPLAY_SIZE as usize]; DISPLAY_SIZE as usize];
+
+/// Sometimes dropped by destroyed enemies. The player catches one by being
+/// underneath it when it reaches the bottom row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum PowerUp {
+    /// Each shot fires three bullets, side by side.
+    Spread,
+    /// No cooldown between shots, and twice as many bullets at once.
+    Rapid,
+    /// Bullets carry on through the enemies they hit.
+    Piercing,
+    /// Enemy bullets bounce off the player.
+    Shield,
+}
+
+impl PowerUp {
+    const ALL: [Self; 4] = [Self::Spread, Self::Rapid, Self::Piercing, Self::Shield];
+
+    pub fn random(rng: &mut Rng) -> Self {
+        Self::ALL[rng.below(Self::ALL.len() as u32) as usize]
+    }
+
+    pub fn falling_visible(elapsed_ms: u32) -> bool {
+        elapsed_ms % FALLING_BLINK_MS < FALLING_BLINK_MS / 2
+    }
+
+    /// While a power-up is active, the cannon blinks. Each one blinks at a
+    /// different rate, so the player can tell which they have.
+    fn blink_ms(self) -> u32 {
+        match self {
+            Self::Spread => 600,
+            Self::Rapid => 150,
+            Self::Piercing => 300,
+            Self::Shield => 1_200,
+        }
+    }
+}
+
+/// A power-up the player has caught, which wears off after a while.
+#[derive(Debug, Clone)]
+pub struct ActivePowerUp {
+    pub kind: PowerUp,
+    remaining_ms: u32,
+}
+
+impl ActivePowerUp {
+    pub fn new(kind: PowerUp, duration_ms: u32) -> Self {
+        Self {
+            kind,
+            remaining_ms: duration_ms,
+        }
+    }
+
+    /// Advance by `elapsed_ms`. Returns true once the power-up has worn off.
+    pub fn tick(&mut self, elapsed_ms: u32) -> bool {
+        self.remaining_ms = self.remaining_ms.saturating_sub(elapsed_ms);
+        self.remaining_ms == 0
+    }
+
+    /// The cannon is lit for most of each blink, so it's still easy to see.
+    pub fn cannon_visible(&self, elapsed_ms: u32) -> bool {
+        let blink_ms = self.kind.blink_ms();
+        elapsed_ms % blink_ms >= blink_ms / 4
+    }
+}
@@ -0,0 +1,404 @@
+use super::{
+    count, in_bounds,
+    power_up::{ActivePowerUp, PowerUp, PowerUpGrid},
+    Boss, Diver, Moves, MysteryShip, Playing, Step, STAY,
+};
+use crate::{
+    buttons::ButtonAction,
+    display::{BoolGrid, DISPLAY_SIZE},
+    game_logic::{
+        enemy::{Enemy, EnemyGrid, EnemyKind},
+        rng::Rng,
+        GameConfig, GamePhase,
+    },
+};
+
+const ALL_STEPS: [Step; 9] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    STAY,
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// The mystery ship and the boss only move sideways.
+const SIDEWAYS_STEPS: [Step; 3] = [(0, -1), STAY, (0, 1)];
+
+/// The first wave of `NORMAL`, with everything cleared off the screen.
+fn empty_playing() -> Playing {
+    let mut playing = Playing::new(&GameConfig::NORMAL, &mut Rng::new(1));
+    playing.enemies = EnemyGrid::default();
+    playing.bullets = BoolGrid::default();
+    playing.enemy_bullets = BoolGrid::default();
+    playing.divers = [const { None }; super::MAX_DIVERS];
+    playing.shields = [0; DISPLAY_SIZE as usize];
+    playing.mystery_ship = None;
+    playing.boss = None;
+    playing.power_ups = PowerUpGrid::default();
+    playing
+}
+
+fn cells() -> impl Iterator<Item = (i8, i8)> {
+    (0..DISPLAY_SIZE).flat_map(|row| (0..DISPLAY_SIZE).map(move |col| (row, col)))
+}
+
+/// Does something moving from `a` by `step_a` run into something moving from
+/// `b` by `step_b`, somewhere on the screen? This follows both of them in
+/// hundredths of an update, rather than working it out like `swept_hit`.
+fn crosses(a: (i8, i8), step_a: Step, b: (i8, i8), step_b: Step) -> bool {
+    let on_screen = |x: i32| (0..=(DISPLAY_SIZE as i32 - 1) * 100).contains(&x);
+    (1..=100).any(|t| {
+        let at = |(row, col): (i8, i8), (drow, dcol): Step| {
+            (
+                row as i32 * 100 + drow as i32 * t,
+                col as i32 * 100 + dcol as i32 * t,
+            )
+        };
+        let (pa, pb) = (at(a, step_a), at(b, step_b));
+        pa == pb && on_screen(pa.0) && on_screen(pa.1)
+    })
+}
+
+/// Fire bullets past a target: from every cell, in every direction, with the
+/// target at every position, moving each of `target_steps`. The bullet must hit
+/// the target exactly when their paths cross.
+///
+/// `place` puts the target at a position, sets how it moves, and returns the
+/// cells it takes up, or `None` if it can't be there.
+fn assert_no_tunnelling(
+    target_steps: &[Step],
+    place: impl Fn(&mut Playing, (i8, i8), Step, &mut Moves) -> Option<BoolGrid>,
+) {
+    let mut rng = Rng::new(1);
+    let mut num_hits = 0;
+    for from in cells() {
+        for bullet_step in ALL_STEPS {
+            // A bullet that leaves the screen disappears anyway, hit or not.
+            if !in_bounds(from.0 + bullet_step.0, from.1 + bullet_step.1) {
+                continue;
+            }
+
+            for target in cells() {
+                for &step in target_steps {
+                    let mut playing = empty_playing();
+                    let mut moves = Moves {
+                        bullets: bullet_step,
+                        ..Moves::default()
+                    };
+                    let Some(grid) = place(&mut playing, target, step, &mut moves) else {
+                        continue;
+                    };
+                    if grid[from.0 as usize][from.1 as usize] {
+                        continue;
+                    }
+
+                    playing.bullets[from.0 as usize][from.1 as usize] = true;
+                    playing.move_all(moves, &mut rng);
+
+                    let hit = count(&playing.bullets) == 0;
+                    let expected = cells()
+                        .filter(|&(row, col)| grid[row as usize][col as usize])
+                        .any(|cell| crosses(from, bullet_step, cell, step));
+                    assert_eq!(
+                        hit, expected,
+                        "bullet from {from:?} by {bullet_step:?}, target at {target:?} by {step:?}"
+                    );
+                    num_hits += hit as u32;
+                }
+            }
+        }
+    }
+    assert_ne!(num_hits, 0);
+}
+
+fn single_cell((row, col): (i8, i8)) -> BoolGrid {
+    let mut grid = BoolGrid::default();
+    grid[row as usize][col as usize] = true;
+    grid
+}
+
+#[test]
+fn bullets_hit_enemies() {
+    assert_no_tunnelling(&ALL_STEPS, |playing, (row, col), step, moves| {
+        playing.enemies[row as usize][col as usize] = Some(Enemy::new(EnemyKind::Grunt));
+        moves.enemies = step;
+        Some(single_cell((row, col)))
+    });
+}
+
+#[test]
+fn bullets_hit_enemy_bullets() {
+    assert_no_tunnelling(&ALL_STEPS, |playing, (row, col), step, moves| {
+        playing.enemy_bullets[row as usize][col as usize] = true;
+        moves.enemy_bullets = step;
+        Some(single_cell((row, col)))
+    });
+}
+
+#[test]
+fn bullets_hit_divers() {
+    assert_no_tunnelling(&ALL_STEPS, |playing, (row, col), step, moves| {
+        let enemy = Enemy::new(EnemyKind::Grunt);
+        playing.divers[0] = Some(Diver::new(row, col, enemy, 100));
+        moves.divers[0] = step;
+        Some(single_cell((row, col)))
+    });
+}
+
+#[test]
+fn bullets_hit_the_mystery_ship() {
+    assert_no_tunnelling(&SIDEWAYS_STEPS, |playing, (row, col), step, moves| {
+        if row != 0 {
+            return None;
+        }
+        let mut ship = MysteryShip::new(100, &mut Rng::new(1));
+        ship.col = col;
+        playing.mystery_ship = Some(ship);
+        moves.mystery_ship = step;
+        Some(single_cell((row, col)))
+    });
+}
+
+#[test]
+fn bullets_hit_the_boss() {
+    let boss_wave = GameConfig::NORMAL.boss.as_ref().unwrap();
+    // The boss is three cells wide, and never leaves the screen.
+    let on_screen = |col: i8| (0..=DISPLAY_SIZE - 3).contains(&col);
+    assert_no_tunnelling(&SIDEWAYS_STEPS, |playing, (row, col), step, moves| {
+        // It always stays in the same rows, so only its column matters.
+        if row != 0 || !on_screen(col) || !on_screen(col + step.1) {
+            return None;
+        }
+        let mut boss = Boss::new(boss_wave);
+        boss.col = col;
+        let grid = boss.grid();
+        playing.boss = Some(boss);
+        moves.boss = step;
+        Some(grid)
+    });
+}
+
+/// Run for `ms` milliseconds. Whatever phase comes next is ignored, so this
+/// carries on even with no enemies left.
+fn run(playing: &mut Playing, ms: u32, rng: &mut Rng) {
+    for _ in 0..ms {
+        let _ = playing.update(1, rng);
+    }
+}
+
+fn with_power_up(kind: PowerUp) -> Playing {
+    let mut playing = empty_playing();
+    playing.power_up = Some(ActivePowerUp::new(
+        kind,
+        playing.config.power_up_duration_ms,
+    ));
+    playing
+}
+
+#[test]
+fn destroyed_enemies_sometimes_drop_power_ups() {
+    const TRIALS: u32 = 10_000;
+    let mut rng = Rng::new(1);
+    let mut drops = 0;
+    for _ in 0..TRIALS {
+        let mut playing = empty_playing();
+        playing.enemies[1][2] = Some(Enemy::new(EnemyKind::Grunt));
+        playing.hit_enemy(1, 2, &mut rng);
+
+        // It drops where the enemy was.
+        drops += playing.power_ups[1][2].is_some() as u32;
+        assert_eq!(
+            playing.power_ups.iter().flatten().flatten().count() as u32,
+            playing.power_ups[1][2].is_some() as u32
+        );
+    }
+
+    let expected = TRIALS / GameConfig::NORMAL.power_up_chance;
+    assert!(
+        drops.abs_diff(expected) < expected / 10,
+        "{drops} drops, expected about {expected}"
+    );
+}
+
+#[test]
+fn power_ups_are_caught_in_the_players_column_on_the_bottom_row() {
+    for (row, col) in cells() {
+        let mut playing = empty_playing();
+        playing.player_x = 2;
+        playing.power_ups[row as usize][col as usize] = Some(PowerUp::Rapid);
+        playing.catch_power_up();
+
+        let caught = (row, col) == (DISPLAY_SIZE - 1, 2);
+        assert_eq!(
+            playing.power_up.is_some(),
+            caught,
+            "power-up at {:?}",
+            (row, col)
+        );
+        assert_eq!(
+            playing.power_ups[row as usize][col as usize].is_none(),
+            caught
+        );
+    }
+}
+
+#[test]
+fn falling_power_ups_are_caught_or_missed() {
+    let fall_ms = DISPLAY_SIZE as u32 * GameConfig::NORMAL.power_up_fall_ms;
+    for col in 0..DISPLAY_SIZE {
+        let mut rng = Rng::new(1);
+        let mut playing = empty_playing();
+        playing.player_x = 2;
+        playing.power_ups[0][col as usize] = Some(PowerUp::Spread);
+        run(&mut playing, fall_ms, &mut rng);
+
+        let kind = playing.power_up.as_ref().map(|p| p.kind);
+        let expected = (col == 2).then_some(PowerUp::Spread);
+        assert_eq!(kind, expected, "power-up falling in column {col}");
+        assert_eq!(playing.power_ups, PowerUpGrid::default());
+    }
+}
+
+#[test]
+fn power_ups_wear_off_after_power_up_duration_ms() {
+    let mut rng = Rng::new(1);
+    let mut playing = empty_playing();
+    playing.power_ups[DISPLAY_SIZE as usize - 1][playing.player_x as usize] =
+        Some(PowerUp::Piercing);
+    playing.catch_power_up();
+
+    run(
+        &mut playing,
+        GameConfig::NORMAL.power_up_duration_ms - 1,
+        &mut rng,
+    );
+    assert!(playing.power_up_active(PowerUp::Piercing));
+    run(&mut playing, 1, &mut rng);
+    assert!(playing.power_up.is_none());
+}
+
+/// The columns of the bullets just fired.
+fn fired(playing: &Playing) -> Vec<usize> {
+    let row = DISPLAY_SIZE as usize - 2;
+    (0..DISPLAY_SIZE as usize)
+        .filter(|&col| playing.bullets[row][col])
+        .collect()
+}
+
+#[test]
+fn spread_fires_three_bullets() {
+    let mut rng = Rng::new(1);
+
+    let mut playing = empty_playing();
+    playing.player_x = 2;
+    playing.player_action(ButtonAction::Fire, &mut rng);
+    assert_eq!(fired(&playing), [2]);
+
+    // `EASY` has room for three bullets.
+    let mut playing = with_power_up(PowerUp::Spread);
+    playing.config = &GameConfig::EASY;
+    playing.player_x = 2;
+    playing.player_action(ButtonAction::Fire, &mut rng);
+    assert_eq!(fired(&playing), [1, 2, 3]);
+
+    // Unless the player is at the edge of the screen.
+    let mut playing = with_power_up(PowerUp::Spread);
+    playing.config = &GameConfig::EASY;
+    playing.player_x = 0;
+    playing.player_action(ButtonAction::Fire, &mut rng);
+    assert_eq!(fired(&playing), [0, 1]);
+}
+
+#[test]
+fn spread_fires_no_more_than_max_bullets() {
+    let max_bullets = GameConfig::NORMAL.max_bullets as u32;
+    for in_flight in 0..=max_bullets {
+        let mut rng = Rng::new(1);
+        let mut playing = with_power_up(PowerUp::Spread);
+        for col in 0..in_flight {
+            playing.bullets[0][col as usize] = true;
+        }
+        playing.player_x = 2;
+        playing.player_action(ButtonAction::Fire, &mut rng);
+
+        // As many as there's room for, from the middle outwards.
+        let expected: &[usize] = match max_bullets - in_flight {
+            0 => &[],
+            1 => &[2],
+            2 => &[1, 2],
+            _ => &[1, 2, 3],
+        };
+        assert_eq!(fired(&playing), expected, "{in_flight} bullets in flight");
+        assert!(count(&playing.bullets) <= max_bullets);
+    }
+}
+
+#[test]
+fn rapid_fires_without_cooldown_and_doubles_max_bullets() {
+    // Fire from every column in turn, as fast as possible.
+    let fire_everywhere = |mut playing: Playing| {
+        let mut rng = Rng::new(1);
+        playing.player_x = 0;
+        for _ in 0..DISPLAY_SIZE {
+            playing.player_action(ButtonAction::Fire, &mut rng);
+            playing.player_action(ButtonAction::Right, &mut rng);
+        }
+        count(&playing.bullets)
+    };
+
+    assert_eq!(fire_everywhere(empty_playing()), 1);
+    let max_bullets = GameConfig::NORMAL.max_bullets as u32;
+    assert_eq!(
+        fire_everywhere(with_power_up(PowerUp::Rapid)),
+        2 * max_bullets
+    );
+}
+
+#[test]
+fn piercing_bullets_carry_on_through_enemies() {
+    let shoot_column = |mut playing: Playing| {
+        let mut rng = Rng::new(1);
+        for row in 0..2 {
+            playing.enemies[row][2] = Some(Enemy::new(EnemyKind::Grunt));
+        }
+        playing.bullets[2][2] = true;
+        for _ in 0..3 {
+            let moves = Moves {
+                bullets: super::UP,
+                ..Moves::default()
+            };
+            playing.move_all(moves, &mut rng);
+        }
+        count(&playing.enemies)
+    };
+
+    assert_eq!(shoot_column(empty_playing()), 1);
+    assert_eq!(shoot_column(with_power_up(PowerUp::Piercing)), 0);
+}
+
+#[test]
+fn shield_stops_enemy_bullets_and_divers() {
+    let bottom = DISPLAY_SIZE - 1;
+    let hit = |mut playing: Playing, by_diver: bool| {
+        let x = playing.player_x;
+        if by_diver {
+            let enemy = Enemy::new(EnemyKind::Grunt);
+            playing.divers[0] = Some(Diver::new(bottom, x, enemy, 100));
+        } else {
+            playing.enemy_bullets[bottom as usize][x as usize] = true;
+        }
+        let lives = playing.lives;
+        let _ = playing.check_gameover();
+        assert!(!playing.player_hit());
+        lives - playing.lives
+    };
+
+    for by_diver in [false, true] {
+        assert_eq!(hit(empty_playing(), by_diver), 1);
+        assert_eq!(hit(with_power_up(PowerUp::Shield), by_diver), 0);
+    }
+}
@@ -0,0 +1,181 @@
+use super::{transition::Transition, Game, GameConfig};
+use crate::{buttons::ButtonAction, display::BoolGrid, log::log};
+
+#[cfg(test)]
+mod tests;
+
+/// How many player actions a recording can hold. Once full, the oldest actions
+/// are overwritten, and the recording can no longer be replayed.
+const MAX_ACTIONS: usize = 256;
+
+/// A player action, along with when it happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct RecordedAction {
+    /// How many times `Game::update` had been called when the action arrived.
+    pub tick: u32,
+    pub action: ButtonAction,
+}
+
+/// Everything the player did during one session of the game, kept in a ring
+/// buffer.
+///
+/// A session starts with a fresh `StartAnimation`. Since `Game` is
+/// deterministic, replaying the actions into a new `Game` with the same seed
+/// reproduces exactly what the player saw. To check this, the recording also
+/// keeps a running hash of every frame that was displayed.
+pub struct Recording {
+    config: &'static GameConfig,
+    /// The state of the game's random number generator when the session began.
+    seed: u32,
+    /// How long the game had been idle when the session began. This decides
+    /// whether the session is a demo game.
+    idle_ms: u32,
+    /// The previous session usually ends with a transition into the new one,
+    /// which is still on screen as this one begins.
+    transition: Option<Transition>,
+    actions: [Option<RecordedAction>; MAX_ACTIONS],
+    /// Total number of actions recorded, including any that were overwritten.
+    num_actions: usize,
+    num_ticks: u32,
+    frame_hash: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum ReplayError {
+    /// Some actions were overwritten, so the recording can't be replayed.
+    Incomplete,
+    /// The replayed game displayed something different than the original.
+    Diverged,
+}
+
+impl Recording {
+    pub const fn new(
+        config: &'static GameConfig,
+        seed: u32,
+        idle_ms: u32,
+        transition: Option<Transition>,
+    ) -> Self {
+        Self {
+            config,
+            seed,
+            idle_ms,
+            transition,
+            actions: [None; MAX_ACTIONS],
+            num_actions: 0,
+            num_ticks: 0,
+            frame_hash: FNV_OFFSET_BASIS,
+        }
+    }
+
+    /// Record a player action, at the current tick.
+    pub fn record_action(&mut self, action: ButtonAction) {
+        self.actions[self.num_actions % MAX_ACTIONS] = Some(RecordedAction {
+            tick: self.num_ticks,
+            action,
+        });
+        self.num_actions += 1;
+    }
+
+    /// Call this once per `Game::update`, with the frame the game displays
+    /// afterwards.
+    pub fn record_tick(&mut self, frame: &BoolGrid) {
+        self.frame_hash = hash_frame(self.frame_hash, frame);
+        self.num_ticks += 1;
+    }
+
+    /// False if the ring buffer overflowed.
+    pub fn is_complete(&self) -> bool {
+        self.num_actions <= MAX_ACTIONS
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn num_ticks(&self) -> u32 {
+        self.num_ticks
+    }
+
+    /// A hash of every frame displayed so far.
+    pub fn frame_hash(&self) -> u32 {
+        self.frame_hash
+    }
+
+    /// The recorded actions, oldest first.
+    pub fn actions(&self) -> impl Iterator<Item = RecordedAction> + '_ {
+        let len = self.num_actions.min(MAX_ACTIONS);
+        let start = self.num_actions - len;
+        (start..self.num_actions).filter_map(|i| self.actions[i % MAX_ACTIONS])
+    }
+
+    /// Send the recording over the debug channel.
+    #[cfg_attr(not(feature = "log-game"), allow(unused_variables))]
+    pub fn dump(&self) {
+        log!(
+            game,
+            debug,
+            "recording: seed {:#x}, idle {} ms, {} ticks, {} actions, complete: {}, frame hash: {:#x}",
+            self.seed,
+            self.idle_ms,
+            self.num_ticks,
+            self.num_actions,
+            self.is_complete(),
+            self.frame_hash,
+        );
+        for a in self.actions() {
+            log!(game, debug, "{}", a);
+        }
+    }
+
+    /// Feed the recorded actions into a fresh `Game`, tick by tick, and check
+    /// that it displays exactly the same frames as the original.
+    pub fn replay(&self) -> Result<Game, ReplayError> {
+        if !self.is_complete() {
+            return Err(ReplayError::Incomplete);
+        }
+
+        let mut game = Game::new(self.config, self.seed).expect("recorded game had a valid config");
+        game.idle_ms = self.idle_ms;
+        game.transition = self.transition.clone();
+        game.recording = Recording::new(
+            self.config,
+            self.seed,
+            self.idle_ms,
+            self.transition.clone(),
+        );
+        let mut actions = self.actions().peekable();
+        for tick in 0..self.num_ticks {
+            while let Some(a) = actions.next_if(|a| a.tick == tick) {
+                game.player_action(a.action);
+            }
+            game.update();
+        }
+
+        if game.recording().frame_hash() == self.frame_hash {
+            Ok(game)
+        } else {
+            Err(ReplayError::Diverged)
+        }
+    }
+}
+
+const FNV_OFFSET_BASIS: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// Fold a frame into a running FNV-1a hash. The frame is first packed into the
+/// low 25 bits of a word, one bit per pixel.
+fn hash_frame(hash: u32, frame: &BoolGrid) -> u32 {
+    let mut bits = 0u32;
+    for row in frame {
+        for &pixel in row {
+            bits = bits << 1 | pixel as u32;
+        }
+    }
+
+    let mut hash = hash;
+    for byte in bits.to_le_bytes() {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
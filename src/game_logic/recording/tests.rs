@@ -0,0 +1,119 @@
+use super::super::{ai::Ai, Game, GameConfig, Phase};
+use crate::buttons::ButtonAction;
+
+/// Long enough to start a few waves, lose a few lives, and so on. In updates.
+const TICKS: u32 = 20_000;
+
+/// Give up waiting for something to happen after this many updates.
+const MAX_TICKS: u32 = 1_000_000;
+
+/// A player who fires every so often, and wanders from side to side.
+fn scripted_action(tick: u32) -> Option<ButtonAction> {
+    match tick % 1_000 {
+        0 | 250 | 500 | 750 => Some(ButtonAction::Fire),
+        100 => Some(ButtonAction::Left),
+        600 => Some(ButtonAction::Right),
+        _ => None,
+    }
+}
+
+fn play(game: &mut Game, ticks: u32, mut player: impl FnMut(u32, &Game) -> Option<ButtonAction>) {
+    for tick in 0..ticks {
+        if let Some(action) = player(tick, game) {
+            game.player_action(action);
+        }
+        game.update();
+    }
+}
+
+/// Update until a new session (and recording) begins.
+fn play_until_new_session(game: &mut Game, mut ai: Option<&mut Ai>) {
+    for _ in 0..MAX_TICKS {
+        let ticks = game.recording().num_ticks();
+        if let Some(action) = ai.as_mut().and_then(|ai| ai.update(game)) {
+            game.player_action(action);
+        }
+        game.update();
+        if game.recording().num_ticks() < ticks {
+            return;
+        }
+    }
+    panic!("no new session after {MAX_TICKS} updates");
+}
+
+#[test]
+fn same_seed_and_actions_give_same_frames() {
+    let mut a = Game::new(&GameConfig::NORMAL, 1).unwrap();
+    let mut b = Game::new(&GameConfig::NORMAL, 1).unwrap();
+    let mut c = Game::new(&GameConfig::NORMAL, 2).unwrap();
+    for game in [&mut a, &mut b, &mut c] {
+        play(game, TICKS, |tick, _| scripted_action(tick));
+    }
+
+    assert_eq!(a.recording().frame_hash(), b.recording().frame_hash());
+    assert_ne!(a.recording().frame_hash(), c.recording().frame_hash());
+}
+
+#[test]
+fn replays_a_session() {
+    for config in [&GameConfig::EASY, &GameConfig::NORMAL, &GameConfig::HARD] {
+        for seed in 1..4 {
+            let mut game = Game::new(config, seed).unwrap();
+            play(&mut game, TICKS, |tick, _| scripted_action(tick));
+
+            let recording = game.recording();
+            assert!(recording.num_actions > 0);
+            let replayed = recording.replay().unwrap();
+            assert_eq!(replayed.recording().num_ticks(), recording.num_ticks());
+        }
+    }
+}
+
+#[test]
+fn replays_a_session_that_starts_with_a_transition() {
+    let mut game = Game::new(&GameConfig::NORMAL, 3).unwrap();
+    play_until_new_session(&mut game, Some(&mut Ai::new()));
+    assert!(game.recording().transition.is_some());
+
+    play(&mut game, TICKS, |tick, _| scripted_action(tick));
+    game.recording().replay().unwrap();
+}
+
+#[test]
+fn replays_attract_mode() {
+    // Nobody touches the buttons, so the enemies win, and the next game is a
+    // demo.
+    let mut game = Game::new(&GameConfig::NORMAL, 4).unwrap();
+    play_until_new_session(&mut game, None);
+    for _ in 0..MAX_TICKS {
+        if game.in_attract_mode() {
+            break;
+        }
+        game.update();
+    }
+    assert!(game.in_attract_mode());
+    assert!(matches!(game.phase, Phase::Playing(_)));
+
+    play(&mut game, TICKS, |_, _| None);
+    assert!(game.in_attract_mode());
+    let replayed = game.recording().replay().unwrap();
+    assert!(replayed.in_attract_mode());
+
+    // Pressing a button starts a real game, in a new session.
+    game.player_action(ButtonAction::Fire);
+    assert!(!game.in_attract_mode());
+    assert_eq!(game.recording().num_ticks(), 0);
+    play(&mut game, TICKS, |tick, _| scripted_action(tick));
+    game.recording().replay().unwrap();
+}
+
+#[test]
+fn incomplete_recordings_cannot_be_replayed() {
+    let mut game = Game::new(&GameConfig::NORMAL, 5).unwrap();
+    play(&mut game, TICKS, |_, _| Some(ButtonAction::Left));
+    assert!(!game.recording().is_complete());
+    assert_eq!(
+        game.recording().replay().err(),
+        Some(super::ReplayError::Incomplete)
+    );
+}
@@ -0,0 +1,44 @@
+/// A small, seedable pseudo-random number generator.
+///
+/// This is Marsaglia's "xorshift32". It's nowhere near good enough for
+/// cryptography, but it's plenty for deciding when enemies fire. More
+/// importantly, the game's behaviour depends only on the seed and the player's
+/// inputs, so any game can be reproduced from its `Recording`.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    /// Never zero, since xorshift would get stuck there.
+    state: u32,
+}
+
+impl Rng {
+    pub const fn new(seed: u32) -> Self {
+        let state = if seed == 0 { 0x2545_f491 } else { seed };
+        Self { state }
+    }
+
+    /// The current state. Passing it to `Rng::new` produces an identical
+    /// generator.
+    pub fn seed(&self) -> u32 {
+        self.state
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// A number in the range `0..n`. Must have `n > 0`.
+    pub fn below(&mut self, n: u32) -> u32 {
+        debug_assert_ne!(n, 0);
+        self.next_u32() % n
+    }
+
+    /// Returns true with probability `numerator / denominator`.
+    pub fn chance(&mut self, numerator: u32, denominator: u32) -> bool {
+        self.below(denominator) < numerator
+    }
+}
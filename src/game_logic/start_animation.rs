@@ -1,49 +1,89 @@
-use super::{playing::Playing, GamePhase, Phase};
-use crate::display::{BoolGrid, DISPLAY_SIZE};
+use super::{
+    animation::{Animation, Effect, Keyframe, Picture},
+    playing::Playing,
+    rng::Rng,
+    GameConfig, GamePhase, Phase,
+};
+use crate::display::BoolGrid;
+
+const O: bool = false;
+const X: bool = true;
+
+const PLAYER: BoolGrid = [
+    [O, O, O, O, O],
+    [O, O, O, O, O],
+    [O, O, O, O, O],
+    [O, O, O, O, O],
+    [O, O, X, O, O],
+];
+
+const ENEMIES: BoolGrid = [
+    [X, X, X, X, O],
+    [O, O, O, O, O],
+    [O, O, O, O, O],
+    [O, O, O, O, O],
+    [O, O, O, O, O],
+];
+
+const PLAYER_AND_ENEMIES: BoolGrid = [
+    [X, X, X, X, O],
+    [O, O, O, O, O],
+    [O, O, O, O, O],
+    [O, O, O, O, O],
+    [O, O, X, O, O],
+];
+
+const KEYFRAMES: &[Keyframe] = &[
+    // Blink player.
+    Keyframe {
+        steps: 4,
+        effect: Effect::Blink {
+            picture: Picture::Blank,
+            blink: Picture::Fixed(PLAYER),
+        },
+    },
+    Keyframe {
+        steps: 4,
+        effect: Effect::Show(Picture::Fixed(PLAYER)),
+    },
+    // Blink enemies.
+    Keyframe {
+        steps: 4,
+        effect: Effect::Blink {
+            picture: Picture::Fixed(PLAYER),
+            blink: Picture::Fixed(ENEMIES),
+        },
+    },
+    Keyframe {
+        steps: 4,
+        effect: Effect::Show(Picture::Fixed(PLAYER_AND_ENEMIES)),
+    },
+];
 
 pub struct StartAnimation {
-    num_updates: u32,
+    config: &'static GameConfig,
+    animation: Animation,
 }
 
 impl StartAnimation {
-    pub fn new() -> Self {
-        Self { num_updates: 0 }
+    pub fn new(config: &'static GameConfig) -> Self {
+        Self {
+            config,
+            animation: Animation::new(KEYFRAMES, config.start_animation_step_ms),
+        }
     }
 }
 
 impl GamePhase for StartAnimation {
     fn display(&self, display_buffer: &mut BoolGrid) {
-        *display_buffer = [[false; 5]; 5];
-
-        // Blink player.
-        if self.num_updates % 2 == 0 || self.num_updates >= 4 {
-            let row = DISPLAY_SIZE - 1;
-            let col = DISPLAY_SIZE / 2;
-            display_buffer[row as usize][col as usize] = true;
-        }
-
-        if self.num_updates >= 8 {
-            // Blink enemies.
-            if self.num_updates % 2 == 0 || self.num_updates >= 12 {
-                // 4 enemies.
-                for col in 0..DISPLAY_SIZE - 1 {
-                    display_buffer[0][col as usize] = true;
-                }
-            }
-        }
-    }
-
-    fn update_timer_ms(&self) -> u32 {
-        250
+        self.animation.display(display_buffer);
     }
 
-    fn update(&mut self) -> Option<Phase> {
-        self.num_updates += 1;
-
-        if self.num_updates < 16 {
-            None
+    fn update(&mut self, elapsed_ms: u32, rng: &mut Rng) -> Option<Phase> {
+        if self.animation.update(elapsed_ms) {
+            Some(Phase::Playing(Playing::new(self.config, rng)))
         } else {
-            Some(Phase::Playing(Playing::new()))
+            None
         }
     }
 }
@@ -0,0 +1,56 @@
+use super::{
+    animation::{for_each_pixel, scattered_order, NUM_PIXELS},
+    config::TransitionEffect,
+};
+use crate::display::{BoolGrid, DISPLAY_SIZE};
+
+/// Blends from the last frame of one phase into the next phase, rather than
+/// cutting straight to it.
+#[derive(Debug, Clone)]
+pub struct Transition {
+    effect: TransitionEffect,
+    /// What was on screen when the previous phase ended.
+    from: BoolGrid,
+    elapsed_ms: u32,
+    duration_ms: u32,
+}
+
+impl Transition {
+    /// `duration_ms` must be non-zero.
+    pub fn new(effect: TransitionEffect, duration_ms: u32, from: BoolGrid) -> Self {
+        debug_assert_ne!(duration_ms, 0);
+        Self {
+            effect,
+            from,
+            elapsed_ms: 0,
+            duration_ms,
+        }
+    }
+
+    /// Advance by `elapsed_ms`. Returns true once the transition is over.
+    pub fn tick(&mut self, elapsed_ms: u32) -> bool {
+        self.elapsed_ms += elapsed_ms;
+        self.elapsed_ms >= self.duration_ms
+    }
+
+    /// `display_buffer` holds the next phase's frame; pixels that the
+    /// transition hasn't reached yet are replaced by the previous phase's.
+    pub fn display(&self, display_buffer: &mut BoolGrid) {
+        for_each_pixel(display_buffer, |row, col, pixel| {
+            // Each pixel switches over once the transition is `order / out_of`
+            // of the way through.
+            let (order, out_of) = match self.effect {
+                TransitionEffect::Wipe => (col as u32, DISPLAY_SIZE as u32),
+                TransitionEffect::Dissolve => (scattered_order(row, col), NUM_PIXELS),
+                TransitionEffect::Iris => {
+                    let centre = DISPLAY_SIZE as usize / 2;
+                    let distance = row.abs_diff(centre).max(col.abs_diff(centre));
+                    (distance as u32, centre as u32 + 1)
+                }
+            };
+            if order * self.duration_ms >= self.elapsed_ms * out_of {
+                *pixel = self.from[row][col];
+            }
+        });
+    }
+}
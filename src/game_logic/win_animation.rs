@@ -1,61 +1,63 @@
-use super::{playing::Playing, start_animation::StartAnimation, GamePhase, Phase};
-use crate::display::{BoolGrid, DISPLAY_SIZE};
+use super::{
+    animation::{Animation, Effect, Keyframe, Picture},
+    playing::Playing,
+    rng::Rng,
+    start_animation::StartAnimation,
+    GamePhase, Phase,
+};
+use crate::display::BoolGrid;
+
+const KEYFRAMES: &[Keyframe] = &[
+    // For a brief moment, show the state of the game when the last enemy was
+    // destroyed. Otherwise, it'd be kinda jarring.
+    Keyframe {
+        steps: 10,
+        effect: Effect::Show(Picture::Scene),
+    },
+    // 4 "sweep" effects.
+    Keyframe {
+        steps: 40,
+        effect: Effect::Sweep,
+    },
+    // Then clear the screen for a moment.
+    Keyframe {
+        steps: 20,
+        effect: Effect::Show(Picture::Blank),
+    },
+];
 
 pub struct WinAnimation {
     game_state: Playing,
-    num_updates: u32,
+    animation: Animation,
 }
 
 impl WinAnimation {
     pub fn new(game_state: Playing) -> Self {
+        let mut scene = BoolGrid::default();
+        game_state.display(&mut scene);
+        let animation =
+            Animation::new(KEYFRAMES, game_state.config.win_animation_step_ms).with_scene(scene);
         Self {
             game_state,
-            num_updates: 0,
+            animation,
         }
     }
 }
 
 impl GamePhase for WinAnimation {
     fn display(&self, display_buffer: &mut BoolGrid) {
-        // For a brief moment, show the state of the game when the last enemy
-        // was destroyed. Otherwise, it'd be kinda jarring.
-        if self.num_updates < 10 {
-            self.game_state.display(display_buffer);
-            return;
-        }
-
-        // After 4 "sweep" effects, clear the screen for a moment.
-        if self.num_updates >= 50 {
-            *display_buffer = [[false; 5]; 5];
-            return;
-        }
-
-        let tick = self.num_updates as usize % 10;
-
-        for row in 0..DISPLAY_SIZE as usize {
-            for col in 0..DISPLAY_SIZE as usize {
-                let sum = row + col;
-
-                // The diagonal "sweep" effect is two pixels wide.
-                let leading_edge = sum == tick;
-                let trailing_edge = sum + 1 == tick;
-
-                display_buffer[row][col] = leading_edge || trailing_edge;
-            }
-        }
+        self.animation.display(display_buffer);
     }
 
-    fn update_timer_ms(&self) -> u32 {
-        50
-    }
-
-    fn update(&mut self) -> Option<Phase> {
-        self.num_updates += 1;
-
-        if self.num_updates < 70 {
+    fn update(&mut self, elapsed_ms: u32, rng: &mut Rng) -> Option<Phase> {
+        if !self.animation.update(elapsed_ms) {
             None
+        } else if let Some(next_wave) = self.game_state.next_wave(rng) {
+            Some(Phase::Playing(next_wave))
         } else {
-            Some(Phase::StartAnimation(StartAnimation::new()))
+            Some(Phase::StartAnimation(StartAnimation::new(
+                self.game_state.config,
+            )))
         }
     }
 }
@@ -0,0 +1,42 @@
+//! Leveled logging, filtered per subsystem.
+//!
+//! Logs are sent over RTT using [`defmt`], which transmits an index into a table
+//! of interned strings plus the raw arguments, instead of formatting text on the
+//! device. This keeps logging cheap enough to use from the display and button
+//! tasks without noticeably perturbing their timing.
+//!
+//! Two filters apply, both at compile time, so that disabled log statements
+//! cost nothing:
+//!
+//! - Each subsystem (`buttons`, `display`, `game`) only logs if the matching
+//!   cargo feature (`log-buttons`, `log-display`, `log-game`) is enabled.
+//! - The `DEFMT_LOG` environment variable sets the most verbose level that gets
+//!   compiled in. The default is set in `.cargo/config.toml`.
+
+/// Log a message on behalf of a subsystem, at the given level.
+///
+/// ```ignore
+/// log!(buttons, trace, "debounce {}", count);
+/// ```
+macro_rules! log {
+    (buttons, $level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "log-buttons")]
+        {
+            ::defmt::$level!($($arg)+);
+        }
+    }};
+    (display, $level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "log-display")]
+        {
+            ::defmt::$level!($($arg)+);
+        }
+    }};
+    (game, $level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "log-game")]
+        {
+            ::defmt::$level!($($arg)+);
+        }
+    }};
+}
+
+pub(crate) use log;
@@ -1,121 +1,350 @@
 #![no_std]
 #![no_main]
 
-use panic_rtt_target as _;
+use defmt_rtt as _;
+use panic_probe as _;
 use rtic::app;
 
 mod buttons;
 mod display;
 mod game_logic;
-mod wrapping_timer;
+mod log;
+mod power;
 
-#[app(device = nrf52833_hal::pac)]
+use rtic_monotonics::nrf::rtc::prelude::*;
+
+// All timing comes from RTC0, which keeps running off the low-frequency clock
+// while the CPU sleeps. Each tick is about 30.5 us.
+nrf_rtc0_monotonic!(Mono);
+
+#[app(device = nrf52833_hal::pac, dispatchers = [SWI0_EGU0, SWI1_EGU1, SWI2_EGU2])]
 mod app {
     use nrf52833_hal::{
+        clocks::Clocks,
         gpio::{p0, p1},
-        pac,
-        prelude::*,
-        timer::Periodic,
-        Timer,
+        gpiote::Gpiote,
+        Rng,
+    };
+    use rtic_monotonics::{fugit::ExtU64, Monotonic};
+    use rtic_sync::{
+        channel::{Receiver, Sender},
+        make_channel, make_signal,
+        signal::{SignalReader, SignalWriter},
     };
-    use rtt_target::rprintln;
 
+    use super::Mono;
     use crate::{
-        buttons::{button::BUTTON_TIMER_US, Buttons},
+        buttons::{
+            button::{DebounceAlgorithm, BUTTON_TIMER_US},
+            AutoRepeat, ButtonEvent, Buttons, GestureTiming,
+        },
         display::{Display, DISPLAY_TIMER_US},
-        game_logic::{Game, GAME_UPDATE_TIMER_US},
+        game_logic::{Game, GameConfig, GAME_UPDATE_TIMER_US},
+        log::log,
+        power::{Power, PowerState},
     };
 
+    /// How many button events can wait for the game to pick them up. The game
+    /// drains them every millisecond, and one update of the buttons produces at
+    /// most five, so this is plenty.
+    const EVENT_QUEUE_LEN: usize = 8;
+
+    /// With `log-buttons`, how often to send the input statistics, in
+    /// milliseconds. They're also sent before going to sleep.
+    const STATS_PERIOD_MS: u32 = 60_000;
+
     #[shared]
     struct Shared {
         game: Game,
+        /// Reports when a button's pin changes, so we don't have to keep
+        /// polling them.
+        gpiote: Gpiote,
     }
 
     #[local]
     struct Local {
-        //
-        // update_display
-        //
-        display_timer: Timer<pac::TIMER0, Periodic>,
         display: Display,
-
-        //
-        // check_buttons
-        //
-        button_timer: Timer<pac::TIMER1, Periodic>,
-        buttons: Buttons<pac::TIMER2>,
-
-        //
-        // game_update
-        //
-        game_update_timer: Timer<pac::TIMER3, Periodic>,
+        buttons: Buttons,
+        pin_changed_tx: SignalWriter<'static, ()>,
     }
 
     #[init]
     fn init(cx: init::Context) -> (Shared, Local) {
-        rtt_target::rtt_init_print!();
-        rprintln!("hello world");
+        log!(
+            game,
+            info,
+            "space-invaders {=str} starting",
+            env!("CARGO_PKG_VERSION")
+        );
 
-        let mut display_timer = Timer::periodic(cx.device.TIMER0);
-        display_timer.enable_interrupt();
-        display_timer.start(DISPLAY_TIMER_US);
+        // The RTC runs off the low-frequency clock, so start that first. The
+        // micro:bit has no 32 kHz crystal, so this uses the internal RC
+        // oscillator.
+        Clocks::new(cx.device.CLOCK).start_lfclk();
+        Mono::start(cx.device.RTC0);
 
-        let mut button_timer = Timer::periodic(cx.device.TIMER1);
-        button_timer.enable_interrupt();
-        button_timer.start(BUTTON_TIMER_US);
+        // Every game is different, but reproducible from this seed.
+        let seed = Rng::new(cx.device.RNG).random_u32();
+        log!(game, info, "seed {:#x}", seed);
 
-        let mut game_update_timer = Timer::periodic(cx.device.TIMER3);
-        game_update_timer.enable_interrupt();
-        game_update_timer.start(GAME_UPDATE_TIMER_US);
+        let config = &GameConfig::NORMAL;
+        // Keep moving while A or B is held. Holding both fires as fast as the
+        // game allows, but no faster than the buttons are read.
+        let fire_period_us = (config.fire_cooldown_ms * 1_000).max(BUTTON_TIMER_US);
+        let auto_repeat = AutoRepeat::new(300_000, 150_000, fire_period_us);
+        let gesture_timing = GestureTiming {
+            double_tap_us: 300_000,
+            long_press_us: 1_000_000,
+        };
 
         let p0 = p0::Parts::new(cx.device.P0);
         let p1 = p1::Parts::new(cx.device.P1);
 
+        let (events_tx, events_rx) = make_channel!(ButtonEvent, EVENT_QUEUE_LEN);
+        // `check_buttons` decides when to sleep, and tells the other tasks.
+        let (display_power_tx, display_power_rx) = make_signal!(PowerState);
+        let (game_power_tx, game_power_rx) = make_signal!(PowerState);
+        let (pin_changed_tx, pin_changed_rx) = make_signal!(());
+        update_display::spawn(display_power_rx).ok();
+        check_buttons::spawn(events_tx, [display_power_tx, game_power_tx], pin_changed_rx).ok();
+        game_update::spawn(events_rx, game_power_rx).ok();
+
         (
-            Shared { game: Game::new() },
+            Shared {
+                game: Game::new(config, seed).expect("invalid game config"),
+                gpiote: Gpiote::new(cx.device.GPIOTE),
+            },
             Local {
-                display_timer,
                 display: Display::new(
                     p0.p0_21, p0.p0_22, p0.p0_15, p0.p0_24, p0.p0_19, p0.p0_28, p0.p0_11, p0.p0_31,
                     p1.p1_05, p0.p0_30,
                 ),
-
-                button_timer,
-                buttons: Buttons::new(p0.p0_14, p0.p0_23, cx.device.TIMER2),
-
-                game_update_timer,
+                buttons: Buttons::new(
+                    p0.p0_14,
+                    p0.p0_23,
+                    [DebounceAlgorithm::DEFAULT; 2],
+                    Some(auto_repeat),
+                    gesture_timing,
+                ),
+                pin_changed_tx,
             },
         )
     }
 
-    #[task(binds = TIMER0, shared = [game], local = [display_timer, display])]
-    fn update_display(mut cx: update_display::Context) {
-        cx.local.display.update(|display_buffer| {
-            cx.shared.game.lock(|game| {
-                game.display(display_buffer);
+    /// With nothing else to do, sleep until the next interrupt: usually the
+    /// RTC, for the next task's deadline, or GPIOTE, for a button. Once
+    /// everything has gone to sleep, the RTC has nothing left to wake us for,
+    /// and only GPIOTE will.
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {
+            cortex_m::asm::wfe();
+        }
+    }
+
+    // Each task below runs forever, mostly waking up at a fixed rate. Waiting
+    // until the next deadline, rather than for a fixed delay, stops the rate
+    // from drifting when a task runs late. See `Schedule`.
+
+    /// Strobing the display is the most time-sensitive, since any hiccup is
+    /// visible as flicker.
+    #[task(priority = 3, shared = [game], local = [display])]
+    async fn update_display(
+        mut cx: update_display::Context,
+        mut power: SignalReader<'static, PowerState>,
+    ) {
+        let mut schedule = Schedule::new(DISPLAY_TIMER_US);
+        loop {
+            if power.try_read() == Some(PowerState::Asleep) {
+                log!(display, debug, "display off");
+                cx.local.display.clear();
+                wait_until_awake(&mut power).await;
+                log!(display, debug, "display on");
+                schedule.restart();
+            }
+
+            cx.local.display.update(|display_buffer| {
+                cx.shared.game.lock(|game| {
+                    game.display(display_buffer);
+                });
             });
-        });
 
-        cx.local.display_timer.reset_event();
+            let next = schedule.next();
+            let now = Mono::now();
+            if now > next {
+                // The row stayed lit for too long, which shows up as flicker.
+                log!(
+                    display,
+                    warn,
+                    "display update {} us late",
+                    (now - next).to_micros()
+                );
+            }
+            Mono::delay_until(next).await;
+        }
     }
 
-    #[task(binds = TIMER1, shared = [game], local = [button_timer, buttons])]
-    fn check_buttons(mut cx: check_buttons::Context) {
-        if let Some(action) = cx.local.buttons.update() {
+    /// Reads the buttons, and decides when to go to sleep.
+    ///
+    /// The buttons are only polled while they're changing, or being held to
+    /// repeat an action. The rest of the time, this waits for GPIOTE to report
+    /// that a pin changed, unless the `poll-buttons` feature is enabled.
+    #[task(priority = 2, shared = [gpiote], local = [buttons])]
+    async fn check_buttons(
+        mut cx: check_buttons::Context,
+        mut events: Sender<'static, ButtonEvent, EVENT_QUEUE_LEN>,
+        mut other_tasks: [SignalWriter<'static, PowerState>; 2],
+        mut pin_changed: SignalReader<'static, ()>,
+    ) {
+        let buttons = cx.local.buttons;
+        let mut power = Power::new(now_ms());
+        let mut schedule = Schedule::new(BUTTON_TIMER_US);
+        let mut stats_sent_ms = now_ms();
+        loop {
+            for event in buttons.update(now_us()) {
+                power.button_pressed(now_ms());
+                if events.try_send(event).is_err() {
+                    log!(buttons, warn, "dropped {}: game isn't keeping up", event);
+                }
+            }
+
+            // Only while the buttons are in use; the statistics don't change
+            // otherwise.
+            if cfg!(feature = "log-buttons")
+                && now_ms().wrapping_sub(stats_sent_ms) >= STATS_PERIOD_MS
+            {
+                buttons.dump_stats();
+                stats_sent_ms = now_ms();
+            }
+
+            if let Some(asleep) = power.update(now_ms()) {
+                log!(game, info, "going to sleep");
+                buttons.dump_stats();
+                stats_sent_ms = now_ms();
+                for task in &mut other_tasks {
+                    task.write(asleep);
+                }
+
+                cx.shared
+                    .gpiote
+                    .lock(|gpiote| buttons.arm_for_press(gpiote));
+                pin_changed.wait_fresh().await;
+                cx.shared.gpiote.lock(|gpiote| buttons.disarm(gpiote));
+
+                log!(game, info, "waking up");
+                if let Some(awake) = power.button_pressed(now_ms()) {
+                    for task in &mut other_tasks {
+                        task.write(awake);
+                    }
+                }
+                schedule.restart();
+                continue;
+            }
+
+            if cfg!(feature = "poll-buttons") || !buttons.is_settled() {
+                Mono::delay_until(schedule.next()).await;
+            } else {
+                // Nothing's happening. Wait for a pin to change, or until it's
+                // time to sleep, which `power.update` notices next time round.
+                cx.shared
+                    .gpiote
+                    .lock(|gpiote| buttons.arm_for_change(gpiote));
+                let sleep_in = (power.sleep_in_ms(now_ms()) as u64).millis();
+                let _ = Mono::timeout_after(sleep_in, pin_changed.wait_fresh()).await;
+                cx.shared.gpiote.lock(|gpiote| buttons.disarm(gpiote));
+                schedule.restart();
+            }
+        }
+    }
+
+    #[task(priority = 1, shared = [game])]
+    async fn game_update(
+        mut cx: game_update::Context,
+        mut events: Receiver<'static, ButtonEvent, EVENT_QUEUE_LEN>,
+        mut power: SignalReader<'static, PowerState>,
+    ) {
+        let mut schedule = Schedule::new(GAME_UPDATE_TIMER_US);
+        loop {
+            if power.try_read() == Some(PowerState::Asleep) {
+                wait_until_awake(&mut power).await;
+                // Whoever woke us up probably doesn't care about the game
+                // that was running when we went to sleep.
+                cx.shared.game.lock(Game::restart);
+                schedule.restart();
+            }
+
             cx.shared.game.lock(|game| {
-                game.player_action(action);
+                while let Ok(event) = events.try_recv() {
+                    if let ButtonEvent::Action(action) = event {
+                        game.player_action(action);
+                    } else {
+                        // Nothing's bound to these yet.
+                        log!(buttons, debug, "{}", event);
+                    }
+                }
+                game.update();
             });
-        }
 
-        cx.local.button_timer.reset_event();
+            Mono::delay_until(schedule.next()).await;
+        }
     }
 
-    #[task(binds = TIMER3, shared = [game], local = [game_update_timer])]
-    fn game_update(mut cx: game_update::Context) {
-        cx.shared.game.lock(|game| {
-            game.update();
+    /// A button's pin changed, after `check_buttons` asked to hear about it.
+    #[task(binds = GPIOTE, priority = 2, shared = [gpiote], local = [pin_changed_tx])]
+    fn pin_changed(mut cx: pin_changed::Context) {
+        cx.shared.gpiote.lock(|gpiote| {
+            gpiote.port().disable_interrupt();
+            gpiote.port().reset_events();
         });
+        cx.local.pin_changed_tx.write(());
+    }
+
+    /// The deadlines of a task that runs every `period_us`.
+    ///
+    /// Each deadline is worked out from when the schedule started, rather than
+    /// by adding a period to the previous one. An RTC tick is about 30.5 us, so
+    /// a period rarely fits a whole number of ticks: 1 ms is 32.768 of them.
+    /// Adding up periods would round each one down to 32 ticks, and run 2.4%
+    /// fast.
+    struct Schedule {
+        period_us: u64,
+        start: <Mono as Monotonic>::Instant,
+        num_periods: u64,
+    }
+
+    impl Schedule {
+        fn new(period_us: u32) -> Self {
+            Self {
+                period_us: period_us as u64,
+                start: Mono::now(),
+                num_periods: 0,
+            }
+        }
+
+        /// Start again from now, e.g. after waiting for something else.
+        fn restart(&mut self) {
+            self.start = Mono::now();
+            self.num_periods = 0;
+        }
+
+        /// When to wake up next.
+        fn next(&mut self) -> <Mono as Monotonic>::Instant {
+            self.num_periods += 1;
+            self.start + (self.num_periods * self.period_us).micros()
+        }
+    }
+
+    /// The current time, wrapping around after about an hour.
+    fn now_us() -> u32 {
+        Mono::now().duration_since_epoch().to_micros() as u32
+    }
+
+    /// The current time, wrapping around after about 7 weeks.
+    fn now_ms() -> u32 {
+        Mono::now().duration_since_epoch().to_millis() as u32
+    }
 
-        cx.local.game_update_timer.reset_event();
+    async fn wait_until_awake(power: &mut SignalReader<'static, PowerState>) {
+        while power.wait().await != PowerState::Awake {}
     }
 }
@@ -0,0 +1,65 @@
+//! Deciding when to go to sleep, to save the batteries.
+//!
+//! This only tracks time and button presses; `main.rs` does the actual
+//! sleeping and waking, so that this part doesn't depend on the hardware.
+//! Timestamps are in milliseconds, and may wrap around.
+
+#[cfg(test)]
+mod tests;
+
+/// If nobody presses a button for this long, go to sleep. In milliseconds.
+///
+/// This is much longer than the game's attract mode timeout, so the demo gets a
+/// good while to draw someone in first.
+pub const SLEEP_AFTER_IDLE_MS: u32 = 5 * 60 * 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum PowerState {
+    Awake,
+    /// The display is blank, and nothing runs until a button is pressed.
+    Asleep,
+}
+
+/// Tracks how long it's been since the last button press.
+pub struct Power {
+    state: PowerState,
+    last_press_ms: u32,
+}
+
+impl Power {
+    pub const fn new(now_ms: u32) -> Self {
+        Self {
+            state: PowerState::Awake,
+            last_press_ms: now_ms,
+        }
+    }
+
+    /// Call this regularly while awake, and at least by `sleep_in_ms`. Returns
+    /// `Some(Asleep)` when it's time to go to sleep.
+    pub fn update(&mut self, now_ms: u32) -> Option<PowerState> {
+        if self.state == PowerState::Awake && self.sleep_in_ms(now_ms) == 0 {
+            self.state = PowerState::Asleep;
+            Some(PowerState::Asleep)
+        } else {
+            None
+        }
+    }
+
+    /// How long until we go to sleep, unless a button is pressed first.
+    pub fn sleep_in_ms(&self, now_ms: u32) -> u32 {
+        let idle_ms = now_ms.wrapping_sub(self.last_press_ms);
+        SLEEP_AFTER_IDLE_MS.saturating_sub(idle_ms)
+    }
+
+    /// Call this whenever a button is pressed. Returns `Some(Awake)` if that
+    /// should wake us up.
+    pub fn button_pressed(&mut self, now_ms: u32) -> Option<PowerState> {
+        self.last_press_ms = now_ms;
+        if self.state == PowerState::Asleep {
+            self.state = PowerState::Awake;
+            Some(PowerState::Awake)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use super::{Power, PowerState, SLEEP_AFTER_IDLE_MS};
+
+#[test]
+fn sleeps_after_being_idle() {
+    let mut power = Power::new(1_000);
+    assert_eq!(power.update(1_000), None);
+    assert_eq!(power.update(1_000 + SLEEP_AFTER_IDLE_MS - 1), None);
+    assert_eq!(
+        power.update(1_000 + SLEEP_AFTER_IDLE_MS),
+        Some(PowerState::Asleep)
+    );
+
+    // Only once.
+    assert_eq!(power.update(1_000 + SLEEP_AFTER_IDLE_MS + 1), None);
+}
+
+#[test]
+fn sleep_in_ms_counts_down_to_zero() {
+    let power = Power::new(1_000);
+    assert_eq!(power.sleep_in_ms(1_000), SLEEP_AFTER_IDLE_MS);
+    assert_eq!(power.sleep_in_ms(1_100), SLEEP_AFTER_IDLE_MS - 100);
+    assert_eq!(power.sleep_in_ms(1_000 + SLEEP_AFTER_IDLE_MS), 0);
+    assert_eq!(power.sleep_in_ms(1_000 + 2 * SLEEP_AFTER_IDLE_MS), 0);
+}
+
+#[test]
+fn button_presses_put_off_sleep() {
+    let mut power = Power::new(0);
+    let press_ms = SLEEP_AFTER_IDLE_MS - 1;
+    assert_eq!(power.button_pressed(press_ms), None);
+    assert_eq!(power.sleep_in_ms(press_ms), SLEEP_AFTER_IDLE_MS);
+
+    assert_eq!(power.update(SLEEP_AFTER_IDLE_MS), None);
+    assert_eq!(power.update(press_ms + SLEEP_AFTER_IDLE_MS - 1), None);
+    assert_eq!(
+        power.update(press_ms + SLEEP_AFTER_IDLE_MS),
+        Some(PowerState::Asleep)
+    );
+}
+
+#[test]
+fn wakes_up_when_a_button_is_pressed() {
+    let mut power = Power::new(0);
+    assert_eq!(power.update(SLEEP_AFTER_IDLE_MS), Some(PowerState::Asleep));
+
+    // However long it's been asleep.
+    let wake_ms = 10 * SLEEP_AFTER_IDLE_MS;
+    assert_eq!(power.button_pressed(wake_ms), Some(PowerState::Awake));
+    assert_eq!(power.button_pressed(wake_ms + 1), None);
+    assert_eq!(power.update(wake_ms + 1), None);
+
+    // And it goes back to sleep the same way.
+    assert_eq!(power.sleep_in_ms(wake_ms + 1), SLEEP_AFTER_IDLE_MS);
+    assert_eq!(
+        power.update(wake_ms + 1 + SLEEP_AFTER_IDLE_MS),
+        Some(PowerState::Asleep)
+    );
+}
+
+#[test]
+fn copes_with_now_ms_wrapping_around() {
+    let start_ms = u32::MAX - 1_000;
+    let mut power = Power::new(start_ms);
+    assert_eq!(
+        power.sleep_in_ms(start_ms.wrapping_add(2_000)),
+        SLEEP_AFTER_IDLE_MS - 2_000
+    );
+    assert_eq!(
+        power.update(start_ms.wrapping_add(SLEEP_AFTER_IDLE_MS - 1)),
+        None
+    );
+    assert_eq!(
+        power.update(start_ms.wrapping_add(SLEEP_AFTER_IDLE_MS)),
+        Some(PowerState::Asleep)
+    );
+
+    // Pressing a button just before the wrap still counts from then.
+    let mut power = Power::new(0);
+    let press_ms = u32::MAX - 1_000;
+    power.button_pressed(press_ms);
+    assert_eq!(
+        power.sleep_in_ms(press_ms.wrapping_add(2_000)),
+        SLEEP_AFTER_IDLE_MS - 2_000
+    );
+}
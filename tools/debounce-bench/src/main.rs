@@ -0,0 +1,230 @@
+//! Runs pin traces through each of the firmware's debouncing algorithms, and
+//! reports how quickly each one noticed the real presses and releases, and how
+//! many bounces got through.
+//!
+//! ```
+//! cargo run                           # every trace in traces/
+//! cargo run -- path/to/trace.txt      # just these traces
+//! ```
+//!
+//! `traces/` describes the format. To record a real button, build the firmware
+//! with `--features log-buttons,poll-buttons` and `DEFMT_LOG=trace`, and keep
+//! the `pin` lines for one button.
+
+use std::{env, fs, path::PathBuf, process::ExitCode};
+
+/// The firmware's own debouncers. They don't touch the hardware, so they build
+/// fine here.
+#[path = "../../../src/buttons/button/debouncer.rs"]
+#[allow(dead_code)] // `settled_state` only matters to the firmware.
+mod debouncer;
+
+/// The debouncers expect to find this next to them. Same as the firmware's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonState {
+    NotPressed,
+    Pressed,
+}
+
+use debouncer::{Algorithm, Debounce, Debouncer, DEBOUNCER_TIMER_US};
+
+const TRACES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/traces");
+
+/// The pin counts as having really changed once it stays put for this long.
+/// In microseconds.
+const SETTLE_US: u32 = 20_000;
+
+/// Keep sampling for this long after the last pin change, so slow debouncers
+/// get a chance to catch up. In microseconds.
+const TAIL_US: u32 = 100_000;
+
+const ALGORITHMS: &[Algorithm] = &[
+    Algorithm::DEFAULT,
+    Algorithm::Counting { samples: 3 },
+    Algorithm::Asymmetric {
+        press_samples: 2,
+        release_samples: 8,
+    },
+    Algorithm::Integrator { max: 5 },
+    Algorithm::Lockout { samples: 10 },
+];
+
+/// A change to the pin's state, at a time in microseconds.
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    time_us: u32,
+    state: ButtonState,
+}
+
+fn main() -> ExitCode {
+    let paths: Vec<PathBuf> = match env::args().skip(1).map(PathBuf::from).collect::<Vec<_>>() {
+        paths if !paths.is_empty() => paths,
+        _ => match fs::read_dir(TRACES_DIR) {
+            Ok(entries) => {
+                let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+                paths.sort();
+                paths
+            }
+            Err(e) => {
+                eprintln!("{TRACES_DIR}: {e}");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    println!(
+        "{:<20} {:<28} {:>9} {:>9} {:>6} {:>6}",
+        "trace", "algorithm", "mean (ms)", "max (ms)", "false", "missed"
+    );
+    for path in &paths {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let trace = match fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| parse(&text))
+        {
+            Ok(trace) => trace,
+            Err(e) => {
+                eprintln!("{}: {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let real = real_edges(&trace);
+        for &algorithm in ALGORITHMS {
+            let reported = debounce(algorithm, &trace);
+            let score = score(&real, &reported);
+            let latency = |us: Option<u32>| match us {
+                Some(us) => format!("{:.1}", us as f64 / 1_000.0),
+                None => "-".to_string(),
+            };
+            println!(
+                "{:<20} {:<28} {:>9} {:>9} {:>6} {:>6}",
+                name,
+                describe(algorithm),
+                latency(score.mean_latency_us),
+                latency(score.max_latency_us),
+                score.false_edges,
+                score.missed,
+            );
+        }
+        println!();
+    }
+    ExitCode::SUCCESS
+}
+
+/// Parse a trace: one `<time in us> <0 or 1>` per line, in order.
+fn parse(text: &str) -> Result<Vec<Edge>, String> {
+    let mut trace: Vec<Edge> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let error = || format!("line {line_number}: expected `<time in us> <0 or 1>`");
+        let (time, level) = line.split_once(char::is_whitespace).ok_or_else(error)?;
+        let time_us: u32 = time.parse().map_err(|_| error())?;
+        let state = match level.trim() {
+            "0" => ButtonState::NotPressed,
+            "1" => ButtonState::Pressed,
+            _ => return Err(error()),
+        };
+        if trace.last().is_some_and(|prev| prev.time_us > time_us) {
+            return Err(format!("line {line_number}: times must not go backwards"));
+        }
+        trace.push(Edge { time_us, state });
+    }
+    Ok(trace)
+}
+
+/// The presses and releases that really happened: each starts at the first
+/// bounce, and ends with the pin settling on a different state than before.
+fn real_edges(trace: &[Edge]) -> Vec<Edge> {
+    let mut edges = Vec::new();
+    let mut settled = ButtonState::NotPressed;
+    let mut bounce_start = None;
+    for (i, edge) in trace.iter().enumerate() {
+        let start = *bounce_start.get_or_insert(edge.time_us);
+        let next_us = trace.get(i + 1).map_or(u32::MAX, |next| next.time_us);
+        if next_us - edge.time_us < SETTLE_US {
+            continue;
+        }
+
+        if edge.state != settled {
+            settled = edge.state;
+            edges.push(Edge {
+                time_us: start,
+                state: settled,
+            });
+        }
+        bounce_start = None;
+    }
+    edges
+}
+
+/// Sample the trace the way the firmware does, and return whatever the
+/// debouncer reported.
+fn debounce(algorithm: Algorithm, trace: &[Edge]) -> Vec<Edge> {
+    let mut debouncer = Debouncer::new(algorithm, ButtonState::NotPressed);
+    let end_us = trace.last().map_or(0, |e| e.time_us) + TAIL_US;
+
+    let mut reported = Vec::new();
+    let mut pin = ButtonState::NotPressed;
+    let mut upcoming = trace.iter().peekable();
+    for time_us in (0..=end_us).step_by(DEBOUNCER_TIMER_US as usize) {
+        while let Some(edge) = upcoming.next_if(|e| e.time_us <= time_us) {
+            pin = edge.state;
+        }
+        if let Some(state) = debouncer.update(pin) {
+            reported.push(Edge { time_us, state });
+        }
+    }
+    reported
+}
+
+struct Score {
+    mean_latency_us: Option<u32>,
+    max_latency_us: Option<u32>,
+    /// Reported changes that didn't really happen.
+    false_edges: usize,
+    /// Real changes that weren't reported.
+    missed: usize,
+}
+
+/// Match up each real edge with the first report of the same change, before the
+/// next real edge.
+fn score(real: &[Edge], reported: &[Edge]) -> Score {
+    let mut latencies = Vec::new();
+    let mut matched = 0;
+    for (i, edge) in real.iter().enumerate() {
+        let until_us = real.get(i + 1).map_or(u32::MAX, |next| next.time_us);
+        let report = reported
+            .iter()
+            .find(|r| r.state == edge.state && (edge.time_us..until_us).contains(&r.time_us));
+        if let Some(report) = report {
+            latencies.push(report.time_us - edge.time_us);
+            matched += 1;
+        }
+    }
+
+    Score {
+        mean_latency_us: (!latencies.is_empty())
+            .then(|| latencies.iter().sum::<u32>() / latencies.len() as u32),
+        max_latency_us: latencies.iter().copied().max(),
+        false_edges: reported.len() - matched,
+        missed: real.len() - matched,
+    }
+}
+
+fn describe(algorithm: Algorithm) -> String {
+    match algorithm {
+        Algorithm::Counting { samples } => format!("counting ({samples})"),
+        Algorithm::Asymmetric {
+            press_samples,
+            release_samples,
+        } => format!("asymmetric ({press_samples}/{release_samples})"),
+        Algorithm::Integrator { max } => format!("integrator ({max})"),
+        Algorithm::Lockout { samples } => format!("lockout ({samples})"),
+    }
+}
@@ -0,0 +1,11 @@
+//! Builds the levels, the same way as the firmware's build script.
+
+use std::path::Path;
+
+#[path = "../../build.rs"]
+#[allow(dead_code)] // The firmware's `main`.
+mod firmware;
+
+fn main() {
+    firmware::generate_levels(Path::new("../.."));
+}
@@ -0,0 +1,169 @@
+//! Tests for the level parser in `build/level_format.rs`. They live here rather
+//! than next to it, since `build.rs` and `tools/level-preview` build the parser
+//! too, and have no tests of their own.
+
+use crate::level_format::{parse, Cell, Movement, SIZE};
+
+const WAVES: &str = include_str!("../../../levels/waves.txt");
+
+#[test]
+fn the_shipped_waves_parse() {
+    let levels = parse(WAVES).unwrap_or_else(|e| panic!("levels/waves.txt: {e}"));
+    let names: Vec<&str> = levels.iter().map(|l| l.name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["training", "checkers", "invasion", "vanguard", "swarm"]
+    );
+
+    let training = &levels[0];
+    assert_eq!(training.line, 18);
+    assert_eq!(training.movement, Movement::Shuffle);
+    assert_eq!(training.speed, 100);
+    assert!(!training.divers);
+    assert_eq!(training.shields, [true, false, true, false, true]);
+    assert_eq!(training.formations.len(), 1);
+    let g = Cell::Grunt;
+    assert_eq!(training.formations[0][0], [g, g, g, g, Cell::Empty]);
+    assert_eq!(training.formations[0][1], [Cell::Empty; SIZE]);
+}
+
+/// Each of these is wrong in some way. The error must point at the right line,
+/// and say what's wrong.
+const MISTAKES: &[(&str, usize, &str)] = &[
+    ("", 1, "no waves defined"),
+    ("# Just a comment.\n", 1, "no waves defined"),
+    ("[level one]\n", 1, "expected a header like `[wave name]`"),
+    ("[wave One]\n", 1, "wave names must be lowercase letters"),
+    (
+        "movement = march\n",
+        1,
+        "expected a header like `[wave name]` first",
+    ),
+    (
+        "[wave a]\nmovement = march\nformation:\nG....\n\n[wave a]\n",
+        6,
+        "wave `a` is already defined on line 1",
+    ),
+    // Unknown directives.
+    ("[wave a]\ncolour = red\n", 2, "unknown key `colour`"),
+    (
+        "[wave a]\nformations\n",
+        2,
+        "expected `key = value`, or `formation:`",
+    ),
+    (
+        "[wave a]\nmovement = sideways\n",
+        2,
+        "unknown movement `sideways`",
+    ),
+    // Formations.
+    (
+        "[wave a]\nmovement = march\nformation:\nGGGG\n",
+        4,
+        "expected 5 columns, found 4",
+    ),
+    (
+        "[wave a]\nmovement = march\nformation:\nGGGGGG\n",
+        4,
+        "expected 5 columns, found 6",
+    ),
+    (
+        "[wave a]\nmovement = march\nformation:\nGGXG.\n",
+        4,
+        "unknown enemy `X`",
+    ),
+    (
+        "[wave a]\nmovement = march\nformation:\nG....\n.....\n.....\n.....\n.....\n",
+        8,
+        "formations can be at most 4 rows tall",
+    ),
+    (
+        "[wave a]\nmovement = march\nformation:\n.....\n",
+        3,
+        "formation has no enemies",
+    ),
+    (
+        "[wave a]\nmovement = march\nformation:\n\n",
+        3,
+        "formation has no rows",
+    ),
+    // Missing fields.
+    (
+        "[wave a]\nformation:\nG....\n",
+        1,
+        "wave `a` has no `movement`",
+    ),
+    (
+        "[wave a]\nmovement = march\n",
+        1,
+        "wave `a` has no formations",
+    ),
+    (
+        "[wave a]\nmovement = march\n\n[wave b]\nmovement = march\nformation:\nG....\n",
+        1,
+        "wave `a` has no formations",
+    ),
+    // Bad values.
+    (
+        "[wave a]\nspeed = fast\n",
+        2,
+        "expected a positive percentage, not `fast`",
+    ),
+    (
+        "[wave a]\nspeed = 0%\n",
+        2,
+        "expected a positive percentage, not `0%`",
+    ),
+    (
+        "[wave a]\nspeed = -50%\n",
+        2,
+        "expected a positive percentage, not `-50%`",
+    ),
+    (
+        "[wave a]\ndivers = maybe\n",
+        2,
+        "expected yes or no, not `maybe`",
+    ),
+    (
+        "[wave a]\nshields = X.X\n",
+        2,
+        "expected 5 columns of shields, found 3",
+    ),
+    (
+        "[wave a]\nshields = X.O.X\n",
+        2,
+        "expected X or `.` for shields, not `O`",
+    ),
+    (
+        "[wave a]\nmovement = march\nmovement = zigzag\n",
+        3,
+        "`movement` is given twice",
+    ),
+];
+
+#[test]
+fn mistakes_point_at_the_right_line() {
+    for &(text, line, message) in MISTAKES {
+        match parse(text) {
+            Ok(_) => panic!("{text:?} parsed, expected an error on line {line}"),
+            Err(e) => {
+                assert_eq!(e.line, line, "{text:?}: {e}");
+                assert!(
+                    e.message.starts_with(message),
+                    "{text:?}: expected {message:?}, got {:?}",
+                    e.message
+                );
+                // This is what ends up after the file name in `cargo::error`.
+                assert_eq!(e.to_string(), format!("line {line}: {}", e.message));
+            }
+        }
+    }
+}
+
+#[test]
+fn comments_and_blank_lines_still_count() {
+    let text =
+        "# A comment.\n\n[wave a]  # Another.\nmovement = march\n\nformation:\nG....\nGGGGGGG\n";
+    let e = parse(text).unwrap_err();
+    assert_eq!(e.line, 8);
+}
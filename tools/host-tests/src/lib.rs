@@ -0,0 +1,52 @@
+//! Runs the firmware's tests on the host, since they can't run on the board.
+//!
+//! ```text
+//! cargo test
+//! ```
+//!
+//! Only the parts of the firmware that don't touch the hardware are built
+//! here, along with the level parser from `build/`. The firmware's tests live
+//! next to the code, in `#[cfg(test)]` modules; the parser's are in
+//! `level_format_tests.rs`.
+
+// A lot of the firmware is only used by the tasks in `main.rs`, or only by the
+// tests.
+#![allow(dead_code, unused_imports)]
+
+#[path = "../../../src"]
+mod firmware {
+    pub mod game_logic;
+    pub mod log;
+    pub mod power;
+}
+
+// The firmware refers to these as `crate::…`.
+use firmware::{game_logic, log};
+
+#[path = "../../../src/buttons"]
+mod buttons {
+    pub mod button;
+    pub mod chords;
+    mod events;
+    mod gestures;
+
+    pub use self::{
+        chords::AutoRepeat,
+        events::{ButtonAction, ButtonEvent, ButtonId},
+        gestures::GestureTiming,
+    };
+}
+
+/// `build.rs` parses `levels/waves.txt` with this.
+#[path = "../../../build/level_format.rs"]
+mod level_format;
+
+#[cfg(test)]
+mod level_format_tests;
+
+/// Stands in for `src/display.rs`, which drives the LEDs.
+mod display {
+    pub const DISPLAY_SIZE: i8 = 5;
+
+    pub type BoolGrid = [[bool; DISPLAY_SIZE as usize]; DISPLAY_SIZE as usize];
+}
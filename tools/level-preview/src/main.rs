@@ -0,0 +1,102 @@
+//! Shows the waves in a level file, as they'd look at the start of the wave.
+//!
+//! ```
+//! cargo run                               # all the waves in levels/waves.txt
+//! cargo run -- path/to/waves.txt swarm    # just `[wave swarm]`, from another file
+//! ```
+
+use std::{env, fs, process::ExitCode};
+
+#[path = "../../../build/level_format.rs"]
+mod level_format;
+
+use level_format::{Cell, Level, Movement, SIZE};
+
+const DEFAULT_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../levels/waves.txt");
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| DEFAULT_PATH.to_string());
+    let only = args.next();
+
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("{path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let levels = match level_format::parse(&text) {
+        Ok(levels) => levels,
+        Err(e) => {
+            eprintln!("{path}:{}: {}", e.line, e.message);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut shown = 0;
+    for level in levels
+        .iter()
+        .filter(|l| only.as_ref().is_none_or(|o| *o == l.name))
+    {
+        preview(level);
+        shown += 1;
+    }
+    if shown == 0 {
+        eprintln!("{path}: no wave named `{}`", only.unwrap_or_default());
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+fn preview(level: &Level) {
+    let movement = match level.movement {
+        Movement::Shuffle => "shuffle",
+        Movement::March => "march",
+        Movement::Zigzag => "zigzag",
+    };
+    let divers = if level.divers { "yes" } else { "no" };
+    println!("[wave {}]  (line {})", level.name, level.line);
+    println!(
+        "movement: {movement}, speed: {}%, divers: {divers}",
+        level.speed
+    );
+    println!();
+
+    // Show the formations side by side, each on a screen with the shields and
+    // the player.
+    let labels: Vec<String> = (1..=level.formations.len())
+        .map(|n| format!("{:<12}", format!("#{n}")))
+        .collect();
+    println!("{}", labels.concat().trim_end());
+
+    for row in 0..SIZE {
+        let screens: Vec<String> = level
+            .formations
+            .iter()
+            .map(|formation| {
+                let pixels: Vec<char> = (0..SIZE)
+                    .map(|col| pixel(level, formation, row, col))
+                    .collect();
+                let line: Vec<String> = pixels.iter().map(char::to_string).collect();
+                format!("{:<12}", line.join(" "))
+            })
+            .collect();
+        println!("{}", screens.concat().trim_end());
+    }
+    println!();
+}
+
+fn pixel(level: &Level, formation: &level_format::Formation, row: usize, col: usize) -> char {
+    let shield_row = SIZE - 2;
+    let player_row = SIZE - 1;
+
+    match formation[row][col] {
+        Cell::Grunt => 'G',
+        Cell::Armoured => 'A',
+        Cell::Splitter => 'S',
+        Cell::Empty if row == shield_row && level.shields[col] => '=',
+        Cell::Empty if row == player_row && col == SIZE / 2 => '^',
+        Cell::Empty => '.',
+    }
+}